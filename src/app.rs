@@ -1,17 +1,46 @@
+use std::{collections::VecDeque, path::PathBuf, time::Instant};
+
 use color_eyre::eyre::Result;
 
 use ratatui::{
-    crossterm::event::{self, KeyCode},
+    crossterm::event::{self, KeyCode, KeyEvent, KeyModifiers, MouseEventKind},
+    layout::Rect,
     symbols::Marker,
     DefaultTerminal,
 };
 use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
 
 use crate::{
-    game::{Car, Game},
-    tui::{ui, Event, Tui},
+    car::Car,
+    diagnostics::Diagnostics,
+    editor::Editor,
+    game::{Game, PickupKind},
+    ghost::Trajectory,
+    input::Input,
+    level::Level,
+    particles::Particles,
+    replay::Replay,
+    rewind::RewindBuffer,
+    settings::Settings,
+    snapshot::Snapshot,
+    telemetry::{TelemetryHistory, TelemetryRecorder},
+    theme::Theme,
+    tui::{canvas_area, ui, Camera, Event, Tui},
 };
 
+// Number of ticks of car speed kept for the HUD sparkline
+const SPEED_HISTORY_LEN: usize = 512;
+// Horizontal span of track shown by the minimap, and its limits when zooming
+const MINIMAP_WINDOW: f64 = 1500.0;
+const MINIMAP_WINDOW_RANGE: (f64, f64) = (500.0, 6000.0);
+// Physics steps and frames per second unless set otherwise
+const DEFAULT_RATE: f64 = 60.0;
+// Distance the editor's cursor moves per key press, and with shift held
+const CURSOR_STEP: f64 = 4.0;
+const FAST_CURSOR_STEP: f64 = 20.0;
+// Longest a ghost run is simulated for, idling runs never end on their own
+const MAX_GHOST_TIME: f64 = 600.0;
+
 #[derive(Clone, Copy)]
 pub enum Action {
     None,
@@ -20,13 +49,82 @@ pub enum Action {
     Render,
     Accelerate,
     Deccelerate,
+    Restart,
+    QuickSave,
+    QuickLoad,
+    Rewind,
+    Pause,
+    TogglePause,
+    Resize(u16, u16),
+    ToggleBackground,
+    CycleTheme,
+    ToggleDebug,
+    ToggleDiagnostics,
+    ToggleGraphs,
+    MinimapZoomIn,
+    MinimapZoomOut,
+    ToggleEditor,
+    MoveCursor(f64, f64),
+    AddPoint,
+    DragPoint,
+    Delete,
+    PlacePickup(PickupKind),
+    PlaceCheckpoint,
+    CycleSurface,
+    SaveLevel,
 }
 
 pub struct App {
-    pub marker: Marker,
+    /// Marker of the game canvas in place of the theme's
+    pub marker: Option<Marker>,
     // pub x: f64,
     // pub y: f64,
     pub game: Game,
+    pub particles: Particles,
+    /// Recent car speeds in tenths of a unit per second, oldest first
+    pub speed_history: VecDeque<u64>,
+    /// Farthest distance reached over all previous runs this session
+    pub personal_best: f64,
+    /// Path of the run that set the personal best, drawn as a ghost car
+    pub ghost: Option<Trajectory>,
+    trajectory: Trajectory,
+    /// Horizontal span of track shown by the minimap
+    pub minimap_window: f64,
+    pub settings: Settings,
+    /// Draw colliders, joints, contacts and velocities over the game
+    pub show_debug: bool,
+    pub show_diagnostics: bool,
+    /// Plot the recent telemetry next to the game
+    pub show_graphs: bool,
+    /// Recent telemetry plotted by the graphs panel
+    pub telemetry_history: TelemetryHistory,
+    /// Level being edited, if the game was started in the editor
+    pub editor: Option<Editor>,
+    /// Whether the editor is shown, instead of test driving its level
+    pub editing: bool,
+    pub paused: bool,
+    pub camera: Camera,
+    terminal_area: Rect,
+    // Physics steps and frames per second
+    tick_rate: f64,
+    frame_rate: f64,
+    // Where the inputs of each run are saved when it ends
+    record_path: Option<PathBuf>,
+    // Replay driving the car instead of the player, and the index of its
+    // next input
+    playback: Option<Replay>,
+    playback_index: usize,
+    // Last quicksave, also written to `save_path` along with the game on exit
+    quicksave: Option<Snapshot>,
+    save_path: Option<PathBuf>,
+    rewind: RewindBuffer,
+    // Writes the state of each step to a file while playing
+    telemetry: Option<TelemetryRecorder>,
+    pub diagnostics: Diagnostics,
+    themes: Vec<Theme>,
+    theme_index: usize,
+    // The selected theme with the settings' palette changes applied
+    active_theme: Theme,
     quitting: bool,
     action_tx: UnboundedSender<Action>,
     action_rx: UnboundedReceiver<Action>,
@@ -34,46 +132,216 @@ pub struct App {
 
 impl Default for App {
     fn default() -> App {
+        App::new(Car::default(), Level::default())
+    }
+}
+
+impl App {
+    pub fn new(car: Car, level: Level) -> App {
         let (action_tx, action_rx) = mpsc::unbounded_channel::<Action>();
-        let car = Car::new(6.0, 6.0, 20.0, 7.0);
         App {
-            // TODO: Customize the marker, I'd like braille but
-            // it breaks overlapping colors
-            marker: Marker::Dot,
+            // Braille looks best but breaks overlapping colors
+            marker: None,
             // x: 0.0,
             // y: 0.0,
-            game: Game::new(car),
+            game: Game::new(car, level).with_time_step(1.0 / DEFAULT_RATE),
+            particles: Particles::default(),
+            speed_history: VecDeque::with_capacity(SPEED_HISTORY_LEN),
+            personal_best: 0.0,
+            ghost: None,
+            trajectory: Trajectory::default(),
+            minimap_window: MINIMAP_WINDOW,
+            settings: Settings::default(),
+            show_debug: false,
+            show_diagnostics: false,
+            show_graphs: false,
+            telemetry_history: TelemetryHistory::new(Settings::default().graph_seconds),
+            editor: None,
+            editing: false,
+            paused: false,
+            camera: Camera::default(),
+            terminal_area: Rect::default(),
+            tick_rate: DEFAULT_RATE,
+            frame_rate: DEFAULT_RATE,
+            record_path: None,
+            playback: None,
+            playback_index: 0,
+            quicksave: None,
+            save_path: None,
+            rewind: RewindBuffer::new(Settings::default().rewind_seconds),
+            telemetry: None,
+            diagnostics: Diagnostics::default(),
+            themes: Theme::presets(),
+            theme_index: 0,
+            active_theme: Theme::default(),
             quitting: false,
             action_tx,
             action_rx,
         }
     }
-}
 
-impl App {
+    /// Selects `theme`, replacing the preset of the same name or otherwise
+    /// adding it to the themes that can be cycled through
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        match self
+            .themes
+            .iter()
+            .position(|preset| preset.name == theme.name)
+        {
+            Some(index) => {
+                self.themes[index] = theme;
+                self.theme_index = index;
+            }
+            None => {
+                self.themes.insert(0, theme);
+                self.theme_index = 0;
+            }
+        }
+        self.refresh_theme();
+        self
+    }
+
+    pub fn with_marker(mut self, marker: Marker) -> Self {
+        self.marker = Some(marker);
+        self
+    }
+
+    /// Sets the physics steps and frames per second. Each step advances the
+    /// simulation by one tick, so the game still runs in real time.
+    pub fn with_rates(mut self, tick_rate: f64, frame_rate: f64) -> Self {
+        self.tick_rate = tick_rate;
+        self.frame_rate = frame_rate;
+        self.game = self.new_game().with_time_step(1.0 / tick_rate);
+        self
+    }
+
+    /// Saves the inputs of each run to `path` when it ends, overwriting the
+    /// previous run
+    pub fn with_recording(mut self, path: PathBuf) -> Self {
+        self.record_path = Some(path);
+        self
+    }
+
+    /// Writes telemetry of every run to `recorder`
+    pub fn with_telemetry(mut self, recorder: TelemetryRecorder) -> Self {
+        self.telemetry = Some(recorder);
+        self
+    }
+
+    /// Opens `editor` instead of starting a run. Tab switches between
+    /// editing the level and test driving it.
+    pub fn with_editor(mut self, editor: Editor) -> Self {
+        self.game = Game::new(*self.game.get_car(), editor.level.clone())
+            .with_time_step(self.game.get_time_step());
+        self.editor = Some(editor);
+        self.editing = true;
+        self
+    }
+
+    /// Plays `replay` back in its own level, car and time step, ignoring the
+    /// player's throttle. Restarting plays it again from the start.
+    pub fn with_replay(mut self, replay: Replay) -> Self {
+        self.game = replay.game();
+        // Keep to real time at the replay's rate
+        self.tick_rate = 1.0 / replay.time_step;
+        self.playback = Some(replay);
+        self.playback_index = 0;
+        self
+    }
+
+    /// Races against a ghost of the run in `replay`, on its level, car and
+    /// time step. The distance the replayed run got is the personal best to
+    /// beat.
+    pub fn with_ghost(mut self, replay: &Replay) -> Self {
+        self.game = replay.game();
+        self.tick_rate = 1.0 / replay.time_step;
+        let mut ghost_game = replay.game();
+        let ghost = Trajectory::simulate(&mut ghost_game, &replay.inputs, MAX_GHOST_TIME);
+        self.personal_best = ghost_game.get_stats().max_distance;
+        self.ghost = Some(ghost);
+        self
+    }
+
+    /// Quicksaves to `path` as well as memory, and saves the game there on
+    /// exit so it can be resumed with [`App::with_snapshot`]
+    pub fn with_save_path(mut self, path: PathBuf) -> Self {
+        self.save_path = Some(path);
+        self
+    }
+
+    /// Carries on from `snapshot`, which quickload also returns to, at the
+    /// rate it was saved at
+    pub fn with_snapshot(mut self, snapshot: Snapshot) -> Self {
+        self.load_snapshot(snapshot.clone());
+        self.tick_rate = 1.0 / self.game.get_time_step();
+        self.quicksave = Some(snapshot);
+        self
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    pub fn with_settings(mut self, settings: Settings) -> Self {
+        self.rewind = RewindBuffer::new(settings.rewind_seconds);
+        self.telemetry_history = TelemetryHistory::new(settings.graph_seconds);
+        self.settings = settings;
+        self.refresh_theme();
+        self
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.active_theme
+    }
+
+    fn refresh_theme(&mut self) {
+        let theme = &self.themes[self.theme_index];
+        self.active_theme = if self.settings.colorblind {
+            theme.colorblind_safe()
+        } else {
+            theme.clone()
+        };
+    }
+
     pub async fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
-        let mut tui = Tui::new().tick_rate(60.0).frame_rate(60.0);
+        let mut tui = Tui::new()
+            .tick_rate(self.tick_rate)
+            .frame_rate(self.frame_rate);
         tui.start();
 
+        let size = terminal.size()?;
+        self.action_tx
+            .send(Action::Resize(size.width, size.height))?;
+
         loop {
             let event = tui.next().await?;
+            self.diagnostics.queue_depth = tui.queue_depth();
 
             match event {
                 // Event::Quit => self.action_tx.send(Action::Quit)?,
                 Event::Tick => self.action_tx.send(Action::Tick)?,
                 Event::Render => self.action_tx.send(Action::Render)?,
-                Event::Key(_) => {
+                Event::Key(_) | Event::Mouse(_) => {
                     let action = self.get_action(event);
-                    self.action_tx.send(action.clone())?;
+                    self.action_tx.send(action)?;
+                }
+                Event::Resize(width, height) => {
+                    self.action_tx.send(Action::Resize(width, height))?;
+                    self.action_tx.send(Action::Render)?;
                 }
+                // Nobody is watching, so stop the clock until the player is back
+                Event::FocusLost => self.action_tx.send(Action::Pause)?,
                 _ => {}
             };
 
             while let Ok(action) = self.action_rx.try_recv() {
-                self.handle_events(action.clone());
+                self.handle_events(action)?;
 
                 if let Action::Render = action {
+                    let started = Instant::now();
                     terminal.draw(|f| ui(f, self))?;
+                    self.diagnostics.draw_time.record(started.elapsed());
+                    self.diagnostics.frames.record(Instant::now());
                 }
             }
 
@@ -82,39 +350,328 @@ impl App {
             }
         }
 
-        Ok(())
+        self.save_recording()?;
+        if let Some(recorder) = &mut self.telemetry {
+            recorder.flush()?;
+        }
+        match &self.save_path {
+            Some(path) => self.game.snapshot().save(path),
+            None => Ok(()),
+        }
     }
 }
 
 impl App {
-    fn handle_events(&mut self, action: Action) {
+    fn handle_events(&mut self, action: Action) -> Result<()> {
         // This timeout makes sure the frame gets updated even without input
         // let timeout = Duration::from_secs_f32(1.0 / 120.0);
 
         match action {
             Action::Quit => self.quitting = true,
-            Action::Tick => self.game.step_physics(),
-            Action::Accelerate => self.game.apply_torque(5000.0),
-            Action::Deccelerate => self.game.apply_torque(-5000.0),
+            Action::Tick if self.paused || self.editing || self.is_run_over() => {}
+            Action::Tick => {
+                self.play_back();
+                let started = Instant::now();
+                self.game.step_physics();
+                self.diagnostics.step_time.record(started.elapsed());
+                self.diagnostics.steps.record(Instant::now());
+                self.particles.update(&self.game);
+                self.trajectory.record(&self.game);
+                self.rewind.record(&self.game);
+                self.telemetry_history.record(&self.game);
+                if let Some(recorder) = &mut self.telemetry {
+                    recorder.record(&self.game)?;
+                }
+                self.record_speed();
+            }
+            Action::Accelerate => self.apply_input(Input::Accelerate),
+            Action::Deccelerate => self.apply_input(Input::Decelerate),
+            Action::Restart => self.restart()?,
+            Action::QuickSave => {
+                let snapshot = self.game.snapshot();
+                if let Some(path) = &self.save_path {
+                    snapshot.save(path)?;
+                }
+                self.quicksave = Some(snapshot);
+            }
+            // Going back to a quicksave is rewinding as far as records go
+            Action::QuickLoad => {
+                if let Some(snapshot) = self.quicksave.clone() {
+                    self.game.rewind(snapshot);
+                    self.rewind.clear();
+                    self.after_time_jump();
+                }
+            }
+            Action::Rewind => {
+                if let Some(snapshot) = self.rewind.step_back(&self.game) {
+                    self.game.rewind(snapshot);
+                    self.after_time_jump();
+                }
+            }
+            Action::Pause => self.paused = true,
+            Action::TogglePause => self.paused = !self.paused,
+            Action::Resize(width, height) => {
+                self.terminal_area = Rect::new(0, 0, width, height);
+                self.fit_camera();
+            }
+            Action::ToggleBackground => {
+                self.settings.show_background = !self.settings.show_background
+            }
+            Action::ToggleDebug => self.show_debug = !self.show_debug,
+            Action::ToggleDiagnostics => {
+                self.show_diagnostics = !self.show_diagnostics;
+                self.fit_camera();
+            }
+            Action::ToggleGraphs => {
+                self.show_graphs = !self.show_graphs;
+                self.fit_camera();
+            }
+            Action::CycleTheme => {
+                self.theme_index = (self.theme_index + 1) % self.themes.len();
+                self.refresh_theme();
+            }
+            Action::MinimapZoomIn => {
+                self.minimap_window = (self.minimap_window / 1.5).max(MINIMAP_WINDOW_RANGE.0)
+            }
+            Action::MinimapZoomOut => {
+                self.minimap_window = (self.minimap_window * 1.5).min(MINIMAP_WINDOW_RANGE.1)
+            }
+            Action::ToggleEditor => self.toggle_editor(),
+            Action::MoveCursor(dx, dy) => self.edit(|editor| editor.move_cursor(dx, dy)),
+            Action::AddPoint => self.edit(Editor::add_point),
+            Action::DragPoint => self.edit(|editor| {
+                editor.toggle_drag();
+                false
+            }),
+            Action::Delete => self.edit(Editor::delete),
+            Action::PlacePickup(kind) => self.edit(|editor| editor.place_pickup(kind)),
+            Action::PlaceCheckpoint => self.edit(Editor::place_checkpoint),
+            Action::CycleSurface => self.edit(Editor::cycle_surface),
+            Action::SaveLevel => {
+                if let Some(editor) = &mut self.editor {
+                    editor.message = Some(match editor.save() {
+                        Ok(()) => format!("Saved {}", editor.path.display()),
+                        Err(error) => format!("{:#}", error),
+                    });
+                }
+            }
             // Action::None => self.game.apply_torque(-2.0),
             _ => {}
         };
 
         // if event::poll(timeout)? {
-        // Ok(())
+        Ok(())
     }
 
-    fn get_action(&self, event: Event) -> Action {
-        if let Event::Key(key) = event {
-            if key.kind != event::KeyEventKind::Release {
-                return match key.code {
-                    KeyCode::Char('q') => Action::Quit,
-                    KeyCode::Right => Action::Accelerate,
-                    KeyCode::Left => Action::Deccelerate,
-                    _ => Action::None,
-                };
+    fn apply_input(&mut self, input: Input) {
+        // The replay has the wheel during playback
+        if self.playback.is_some() {
+            return;
+        }
+        self.game.apply_input(input);
+    }
+
+    // Applies the replay's inputs that are due before the next physics step
+    fn play_back(&mut self) {
+        let Some(replay) = &self.playback else {
+            return;
+        };
+        while let Some(timed) = replay
+            .inputs
+            .get(self.playback_index)
+            .filter(|timed| timed.tick <= self.game.get_tick())
+        {
+            self.game.apply_input(timed.input);
+            self.playback_index += 1;
+        }
+    }
+
+    fn load_snapshot(&mut self, snapshot: Snapshot) {
+        self.game = Game::restore(snapshot);
+        self.rewind.clear();
+        // The snapshot can be from another run, so the poses recorded so far
+        // don't lead up to it
+        self.trajectory.clear();
+        self.after_time_jump();
+    }
+
+    // Brings everything that follows the game along after it moved to
+    // another point in time
+    fn after_time_jump(&mut self) {
+        let tick = self.game.get_tick();
+        self.trajectory.truncate(tick);
+        self.telemetry_history.truncate(tick);
+        if let Some(replay) = &self.playback {
+            self.playback_index = replay.inputs.partition_point(|timed| timed.tick < tick);
+        }
+        self.particles.clear();
+        self.speed_history.clear();
+    }
+
+    fn save_recording(&self) -> Result<()> {
+        match &self.record_path {
+            Some(path) => Replay::of(&self.game).save(path),
+            None => Ok(()),
+        }
+    }
+
+    fn fit_camera(&mut self) {
+        self.camera = Camera::fit(canvas_area(self.terminal_area, self));
+    }
+
+    /// Whether the car crashed or finished, which stops the run until it's
+    /// restarted
+    pub fn is_run_over(&self) -> bool {
+        self.game.get_crash().is_some() || self.game.is_finished()
+    }
+
+    // A fresh game with the current car, level and time step, or the
+    // replay's game during playback
+    fn new_game(&self) -> Game {
+        match &self.playback {
+            Some(replay) => replay.game(),
+            None => Game::new(*self.game.get_car(), self.game.get_level().clone())
+                .with_time_step(self.game.get_time_step()),
+        }
+    }
+
+    fn restart(&mut self) -> Result<()> {
+        self.save_recording()?;
+        // Rewound runs don't count as records, and neither do runs loaded
+        // partway through as their ghost would be out of step
+        let stats = self.game.get_stats();
+        if stats.rewinds == 0
+            && self.trajectory.is_from_start()
+            && stats.max_distance > self.personal_best
+        {
+            self.personal_best = stats.max_distance;
+            self.ghost = Some(std::mem::take(&mut self.trajectory));
+        }
+        self.game = self.new_game();
+        self.clear_run();
+        Ok(())
+    }
+
+    // Forgets everything that followed the previous run
+    fn clear_run(&mut self) {
+        self.trajectory.clear();
+        self.rewind.clear();
+        self.playback_index = 0;
+        self.particles.clear();
+        self.speed_history.clear();
+        self.telemetry_history.clear();
+    }
+
+    // Switches between editing and test driving, both starting a fresh run
+    // of the edited level. Unplayable levels can't be test driven.
+    fn toggle_editor(&mut self) {
+        let Some(editor) = &mut self.editor else {
+            return;
+        };
+        if self.editing {
+            if let Err(error) = editor.level.validate() {
+                editor.message = Some(format!("{:#}", error));
+                return;
             }
+            editor.dragging = None;
+            editor.message = None;
+        }
+
+        self.editing = !self.editing;
+        self.game = Game::new(*self.game.get_car(), editor.level.clone())
+            .with_time_step(self.game.get_time_step());
+        // Records from before the edit were set on another track
+        self.personal_best = 0.0;
+        self.ghost = None;
+        self.clear_run();
+    }
+
+    // Applies a change to the edited level, rebuilding the game shown under
+    // the editor when `change` says the level changed
+    fn edit(&mut self, change: impl FnOnce(&mut Editor) -> bool) {
+        let Some(editor) = self.editor.as_mut().filter(|_| self.editing) else {
+            return;
+        };
+        if change(editor) {
+            self.game = Game::new(*self.game.get_car(), editor.level.clone())
+                .with_time_step(self.game.get_time_step());
+        }
+    }
+
+    fn record_speed(&mut self) {
+        if self.speed_history.len() == SPEED_HISTORY_LEN {
+            self.speed_history.pop_front();
+        }
+        self.speed_history
+            .push_back((self.game.get_car_speed() * 10.0).round() as u64);
+    }
+
+    fn get_action(&self, event: Event) -> Action {
+        if let Event::Mouse(mouse) = event {
+            return match mouse.kind {
+                MouseEventKind::ScrollUp => Action::MinimapZoomIn,
+                MouseEventKind::ScrollDown => Action::MinimapZoomOut,
+                _ => Action::None,
+            };
+        }
+        let Event::Key(key) = event else {
+            return Action::None;
         };
-        Action::None
+        if key.kind == event::KeyEventKind::Release {
+            return Action::None;
+        }
+        if self.editing {
+            return editor_action(key);
+        }
+
+        match key.code {
+            KeyCode::Char('q') => Action::Quit,
+            KeyCode::Tab => Action::ToggleEditor,
+            KeyCode::Right => Action::Accelerate,
+            KeyCode::Left => Action::Deccelerate,
+            KeyCode::Char('r') => Action::Restart,
+            KeyCode::Char('p') => Action::TogglePause,
+            KeyCode::Char('s') => Action::QuickSave,
+            KeyCode::Char('l') => Action::QuickLoad,
+            // Repeats while held, rewinding a step at a time
+            KeyCode::Char('z') => Action::Rewind,
+            KeyCode::Char('b') => Action::ToggleBackground,
+            KeyCode::Char('t') => Action::CycleTheme,
+            KeyCode::Char('d') => Action::ToggleDebug,
+            KeyCode::Char('f') => Action::ToggleDiagnostics,
+            KeyCode::Char('g') => Action::ToggleGraphs,
+            KeyCode::Char('=') => Action::MinimapZoomIn,
+            KeyCode::Char('-') => Action::MinimapZoomOut,
+            _ => Action::None,
+        }
+    }
+}
+
+// Keys while the level editor is shown
+fn editor_action(key: KeyEvent) -> Action {
+    let step = if key.modifiers.contains(KeyModifiers::SHIFT) {
+        FAST_CURSOR_STEP
+    } else {
+        CURSOR_STEP
+    };
+    match key.code {
+        KeyCode::Char('q') => Action::Quit,
+        KeyCode::Tab => Action::ToggleEditor,
+        KeyCode::Left => Action::MoveCursor(-step, 0.0),
+        KeyCode::Right => Action::MoveCursor(step, 0.0),
+        KeyCode::Up => Action::MoveCursor(0.0, step),
+        KeyCode::Down => Action::MoveCursor(0.0, -step),
+        KeyCode::Char('a') => Action::AddPoint,
+        KeyCode::Char('m') => Action::DragPoint,
+        KeyCode::Char('x') | KeyCode::Delete | KeyCode::Backspace => Action::Delete,
+        KeyCode::Char('c') => Action::PlacePickup(PickupKind::Coin),
+        KeyCode::Char('f') => Action::PlacePickup(PickupKind::Fuel),
+        KeyCode::Char('k') => Action::PlaceCheckpoint,
+        KeyCode::Char('s') => Action::CycleSurface,
+        KeyCode::Char('w') => Action::SaveLevel,
+        KeyCode::Char('t') => Action::CycleTheme,
+        KeyCode::Char('=') => Action::MinimapZoomIn,
+        KeyCode::Char('-') => Action::MinimapZoomOut,
+        _ => Action::None,
     }
 }
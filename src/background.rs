@@ -0,0 +1,207 @@
+use std::f64::consts::TAU;
+
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::terrain::lattice_value;
+
+// Horizontal distance between mountain silhouette samples
+const SAMPLE_SPACING: f64 = 8.0;
+// Relative frequencies and strengths of the waves making up a mountain range
+const MOUNTAIN_HARMONICS: [(f64, f64); 4] = [(2.0, 1.0), (3.0, 0.6), (5.0, 0.35), (8.0, 0.2)];
+
+/// Distant mountains, nearer foothills and clouds, from farthest to nearest
+pub const DEFAULT_LAYERS: [LayerSpec; 3] = [
+    LayerSpec {
+        kind: LayerKind::Clouds,
+        parallax: 0.05,
+        period: 900.0,
+        base: 70.0,
+        height: 12.0,
+        count: 5,
+    },
+    LayerSpec {
+        kind: LayerKind::Mountains,
+        parallax: 0.1,
+        period: 1600.0,
+        base: 15.0,
+        height: 45.0,
+        count: 0,
+    },
+    LayerSpec {
+        kind: LayerKind::Mountains,
+        parallax: 0.3,
+        period: 1000.0,
+        base: 0.0,
+        height: 25.0,
+        count: 0,
+    },
+];
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LayerKind {
+    Mountains,
+    Clouds,
+}
+
+/// How a level's background layer is generated, the layer's shape comes
+/// from the level's seed
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct LayerSpec {
+    pub kind: LayerKind,
+    /// Fraction of the camera's movement the layer follows, from 0 to 1
+    pub parallax: f64,
+    /// Distance after which the layer repeats
+    pub period: f64,
+    /// Height of the foot of the mountains or of the lowest clouds
+    pub base: f64,
+    /// Height of the mountains or how far apart in height the clouds are
+    pub height: f64,
+    /// Clouds per period, unused by mountains
+    #[serde(default)]
+    pub count: usize,
+}
+
+impl LayerSpec {
+    /// Checks that the layer can be generated
+    pub fn validate(&self) -> Result<()> {
+        if !(0.0..=1.0).contains(&self.parallax) {
+            return Err(eyre!(
+                "background parallax must be between 0 and 1, got {}",
+                self.parallax
+            ));
+        }
+        if !self.period.is_finite() || self.period < SAMPLE_SPACING {
+            return Err(eyre!(
+                "background period must be at least {}, got {}",
+                SAMPLE_SPACING,
+                self.period
+            ));
+        }
+        if !self.base.is_finite() || !self.height.is_finite() || self.height < 0.0 {
+            return Err(eyre!(
+                "background base and height must be finite and the height not negative"
+            ));
+        }
+        if self.kind == LayerKind::Clouds && self.count == 0 {
+            return Err(eyre!("background clouds need a count of at least one"));
+        }
+        Ok(())
+    }
+}
+
+/// Scenery drawn behind the terrain, repeating every `period` units
+pub struct BackgroundLayer {
+    pub kind: LayerKind,
+    /// Fraction of the camera's movement the layer follows, smaller values
+    /// look farther away
+    pub parallax: f64,
+    pub period: f64,
+    /// Silhouette for mountains or cloud centers, in layer space
+    pub points: Vec<(f64, f64)>,
+}
+
+impl BackgroundLayer {
+    fn mountains(seed: u64, spec: &LayerSpec) -> Self {
+        let LayerSpec {
+            parallax,
+            period,
+            base,
+            height,
+            ..
+        } = *spec;
+        let total: f64 = MOUNTAIN_HARMONICS
+            .iter()
+            .map(|(_, strength)| strength)
+            .sum();
+        let samples = (period / SAMPLE_SPACING) as usize;
+        let points = (0..samples)
+            .map(|i| {
+                let x = i as f64 * SAMPLE_SPACING;
+                // Whole numbers of waves per period keep the range seamless
+                let wave: f64 = MOUNTAIN_HARMONICS
+                    .iter()
+                    .enumerate()
+                    .map(|(k, (frequency, strength))| {
+                        let phase = lattice_value(seed, k as i64) * TAU;
+                        strength * (frequency * TAU * x / period + phase).sin()
+                    })
+                    .sum();
+                (x, base + height * (0.5 + 0.5 * wave / total))
+            })
+            .collect();
+
+        BackgroundLayer {
+            kind: LayerKind::Mountains,
+            parallax,
+            period,
+            points,
+        }
+    }
+
+    fn clouds(seed: u64, spec: &LayerSpec) -> Self {
+        let LayerSpec {
+            parallax,
+            period,
+            base,
+            height: spread,
+            count,
+            ..
+        } = *spec;
+        let spacing = period / count as f64;
+        let points = (0..count)
+            .map(|i| {
+                let jitter = lattice_value(seed, 2 * i as i64) * 0.4;
+                let height = lattice_value(seed, 2 * i as i64 + 1);
+                ((i as f64 + 0.5 + jitter) * spacing, base + spread * height)
+            })
+            .collect();
+
+        BackgroundLayer {
+            kind: LayerKind::Clouds,
+            parallax,
+            period,
+            points,
+        }
+    }
+
+    /// Translation from layer space to world space for a camera at `camera`
+    pub fn offset(&self, camera: (f64, f64)) -> (f64, f64) {
+        (
+            camera.0 * (1.0 - self.parallax),
+            camera.1 * (1.0 - self.parallax),
+        )
+    }
+
+    /// Point `index` of the layer in layer space, indices past the end wrap
+    /// around into the next repetition of the layer.
+    pub fn point(&self, index: i64) -> (f64, f64) {
+        let len = self.points.len() as i64;
+        let (x, y) = self.points[index.rem_euclid(len) as usize];
+        (x + index.div_euclid(len) as f64 * self.period, y)
+    }
+
+    /// Range of point indices covering layer space between `from` and `to`
+    pub fn indices(&self, from: f64, to: f64) -> std::ops::RangeInclusive<i64> {
+        let len = self.points.len() as i64;
+        let first = (from / self.period).floor() as i64 * len - 1;
+        let last = ((to / self.period).floor() as i64 + 1) * len;
+        first..=last
+    }
+}
+
+/// Layers following `specs`, each shaped by its own seed derived from `seed`
+pub fn generate(seed: u64, specs: &[LayerSpec]) -> Vec<BackgroundLayer> {
+    specs
+        .iter()
+        .enumerate()
+        .map(|(i, spec)| {
+            let seed = seed.wrapping_add(i as u64);
+            match spec.kind {
+                LayerKind::Mountains => BackgroundLayer::mountains(seed, spec),
+                LayerKind::Clouds => BackgroundLayer::clouds(seed, spec),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,108 @@
+//! Runs the simulation without a terminal and prints the result as JSON
+
+use std::path::PathBuf;
+
+use clap::Parser;
+use color_eyre::eyre::Result;
+use ratatui_hillclimb::{
+    input::Script, sim, telemetry::TelemetryRecorder, Car, Game, Level, Replay,
+};
+
+/// Simulate a hill climb run as fast as possible and print the result as JSON
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Level file to play
+    #[arg(long, value_name = "FILE", conflicts_with = "seed")]
+    level: Option<PathBuf>,
+
+    /// Seed for the generated terrain of the default level
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Vehicle file with the car's dimensions
+    #[arg(long, value_name = "FILE")]
+    vehicle: Option<PathBuf>,
+
+    /// Script file with the inputs to apply
+    #[arg(long, value_name = "FILE")]
+    script: Option<PathBuf>,
+
+    /// Replay file to play back in its own level, car and time step
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["level", "seed", "vehicle", "script", "tick_rate"]
+    )]
+    replay: Option<PathBuf>,
+
+    /// Physics steps per simulated second
+    #[arg(long, value_name = "HZ", default_value_t = 60.0, value_parser = parse_positive)]
+    tick_rate: f64,
+
+    /// Simulated seconds after which the run is stopped
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value_t = sim::DEFAULT_DURATION,
+        value_parser = parse_positive
+    )]
+    duration: f64,
+
+    /// Write the state of every physics step to a .csv or .jsonl file
+    #[arg(long, value_name = "FILE")]
+    telemetry: Option<PathBuf>,
+
+    /// Telemetry rows per simulated second instead of one per step, from 1
+    /// to 1000
+    #[arg(long, value_name = "HZ", requires = "telemetry")]
+    telemetry_rate: Option<f64>,
+
+    /// Continue telemetry in a new numbered file once one reaches this size
+    #[arg(long, value_name = "BYTES", requires = "telemetry")]
+    telemetry_max_bytes: Option<u64>,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let mut telemetry = cli
+        .telemetry
+        .as_deref()
+        .map(|path| TelemetryRecorder::open(path, cli.telemetry_rate, cli.telemetry_max_bytes))
+        .transpose()?;
+
+    if let Some(path) = &cli.replay {
+        let replay = Replay::load(path)?;
+        let result = sim::run(
+            &mut replay.game(),
+            &replay.inputs,
+            cli.duration,
+            telemetry.as_mut(),
+        )?;
+        println!("{}", serde_json::to_string(&result)?);
+        return Ok(());
+    }
+
+    let level = Level::load_or_generate(cli.level.as_deref(), cli.seed)?;
+    let car = Car::load_or_default(cli.vehicle.as_deref())?;
+    let script = match &cli.script {
+        Some(path) => Script::load(path)?,
+        None => Script::default(),
+    };
+
+    let mut game = Game::new(car, level).with_time_step(1.0 / cli.tick_rate);
+    let result = sim::run(&mut game, &script.inputs, cli.duration, telemetry.as_mut())?;
+    println!("{}", serde_json::to_string(&result)?);
+    Ok(())
+}
+
+fn parse_positive(value: &str) -> Result<f64, String> {
+    let number: f64 = value
+        .parse()
+        .map_err(|_| format!("{value} is not a number"))?;
+    if !number.is_finite() || number <= 0.0 {
+        return Err("must be a positive number".to_string());
+    }
+    Ok(number)
+}
@@ -0,0 +1,101 @@
+use crate::surface::Surface;
+
+/// Distance each biome lasts on generated terrain before the next one starts
+pub const BIOME_LENGTH: f64 = 800.0;
+// Distance over the end of a biome in which its hills blend into the next's
+const TRANSITION_LENGTH: f64 = 150.0;
+
+/// Kind of landscape generated terrain passes through. Biomes follow each
+/// other in the order they're declared, starting over after the last one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Biome {
+    Countryside,
+    Desert,
+    Arctic,
+    Moon,
+    Cave,
+}
+
+impl Biome {
+    pub const ALL: [Biome; 5] = [
+        Biome::Countryside,
+        Biome::Desert,
+        Biome::Arctic,
+        Biome::Moon,
+        Biome::Cave,
+    ];
+
+    /// Biome of generated terrain at `x`. The ground behind the first
+    /// biome boundary is all countryside.
+    pub fn at(x: f64) -> Biome {
+        let index = (x / BIOME_LENGTH).floor().max(0.0) as usize;
+        Biome::ALL[index % Biome::ALL.len()]
+    }
+
+    /// The biome at `x` and the one after it, with how far the hills at `x`
+    /// have blended into the next biome's, from 0 to 1
+    pub fn transition(x: f64) -> (Biome, Biome, f64) {
+        let biome = Biome::at(x);
+        let next = Biome::at(x + BIOME_LENGTH);
+        let into_biome = x.rem_euclid(BIOME_LENGTH);
+        let t =
+            ((into_biome - (BIOME_LENGTH - TRANSITION_LENGTH)) / TRANSITION_LENGTH).clamp(0.0, 1.0);
+        // Smoothstep so the slope doesn't kink where the blend starts and ends
+        (biome, next, t * t * (3.0 - 2.0 * t))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Biome::Countryside => "countryside",
+            Biome::Desert => "desert",
+            Biome::Arctic => "arctic",
+            Biome::Moon => "moon",
+            Biome::Cave => "cave",
+        }
+    }
+
+    /// Height of the hills relative to the countryside's
+    pub fn amplitude(self) -> f64 {
+        match self {
+            Biome::Countryside => 1.0,
+            Biome::Desert => 0.7,
+            Biome::Arctic => 0.25,
+            Biome::Moon => 1.4,
+            Biome::Cave => 0.4,
+        }
+    }
+
+    /// How often the hills come relative to the countryside's
+    pub fn frequency(self) -> f64 {
+        match self {
+            Biome::Countryside => 1.0,
+            Biome::Desert => 0.6,
+            Biome::Arctic => 0.5,
+            Biome::Moon => 0.6,
+            Biome::Cave => 1.8,
+        }
+    }
+
+    pub fn surface(self) -> Surface {
+        match self {
+            Biome::Countryside => Surface::Grass,
+            Biome::Desert => Surface::Sand,
+            Biome::Arctic => Surface::Ice,
+            // Bare rock grips about as well as asphalt, which the wheels need
+            // with so little weight on them
+            Biome::Moon => Surface::Asphalt,
+            Biome::Cave => Surface::Dirt,
+        }
+    }
+
+    /// Gravity as a fraction of the level's
+    pub fn gravity(self) -> f64 {
+        match self {
+            Biome::Countryside | Biome::Desert | Biome::Arctic => 1.0,
+            // About twice the real moon's, which leaves too little grip to
+            // get anywhere
+            Biome::Moon => 0.35,
+            Biome::Cave => 1.1,
+        }
+    }
+}
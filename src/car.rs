@@ -0,0 +1,72 @@
+use std::path::Path;
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+
+/// Dimensions of the car. Vehicle files are TOML with the same fields,
+/// anything they leave out is taken from the default car.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Car {
+    /// The rear wheel is driven by the motor
+    pub rear_wheel_radius: f64,
+    pub front_wheel_radius: f64,
+    /// Half the size of the body, the wheels are attached at its lower
+    /// corners
+    pub body_half_width: f64,
+    pub body_half_height: f64,
+}
+
+impl Car {
+    pub fn new(
+        rear_wheel_radius: f64,
+        front_wheel_radius: f64,
+        body_half_width: f64,
+        body_half_height: f64,
+    ) -> Car {
+        Car {
+            rear_wheel_radius,
+            front_wheel_radius,
+            body_half_width,
+            body_half_height,
+        }
+    }
+
+    /// Reads a vehicle file, checking that every dimension is positive
+    pub fn load(path: &Path) -> Result<Car> {
+        let contents = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Unable to read vehicle {}", path.display()))?;
+        let car: Car = toml::from_str(&contents)
+            .wrap_err_with(|| format!("Invalid vehicle {}", path.display()))?;
+        car.validate()
+            .wrap_err_with(|| format!("Invalid vehicle {}", path.display()))?;
+        Ok(car)
+    }
+
+    /// The vehicle file at `path` if there is one, or otherwise the default
+    /// car
+    pub fn load_or_default(path: Option<&Path>) -> Result<Car> {
+        path.map_or(Ok(Car::default()), Car::load)
+    }
+
+    pub(crate) fn validate(&self) -> Result<()> {
+        let dimensions = [
+            ("rear_wheel_radius", self.rear_wheel_radius),
+            ("front_wheel_radius", self.front_wheel_radius),
+            ("body_half_width", self.body_half_width),
+            ("body_half_height", self.body_half_height),
+        ];
+        for (name, value) in dimensions {
+            if !value.is_finite() || value <= 0.0 {
+                return Err(eyre!("{} must be positive, got {}", name, value));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Car {
+    fn default() -> Car {
+        Car::new(6.0, 6.0, 20.0, 7.0)
+    }
+}
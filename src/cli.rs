@@ -0,0 +1,168 @@
+use std::path::PathBuf;
+
+use clap::{Parser, ValueEnum};
+use ratatui::symbols::Marker;
+use ratatui_hillclimb::sim;
+
+// Limits on the tick and frame rates, in Hz
+const RATE_RANGE: (f64, f64) = (1.0, 1000.0);
+
+/// Drive a car over procedurally generated hills in the terminal
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Level file to play
+    #[arg(long, value_name = "FILE", conflicts_with = "seed")]
+    pub level: Option<PathBuf>,
+
+    /// Seed for the generated terrain of the default level
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Vehicle file with the car's dimensions
+    #[arg(long, value_name = "FILE")]
+    pub vehicle: Option<PathBuf>,
+
+    /// Physics steps per second
+    #[arg(long, value_name = "HZ", default_value_t = 60.0, value_parser = parse_rate)]
+    pub tick_rate: f64,
+
+    /// Frames drawn per second
+    #[arg(long, value_name = "HZ", default_value_t = 60.0, value_parser = parse_rate)]
+    pub frame_rate: f64,
+
+    /// Marker used to draw the game canvas, in place of the theme's
+    #[arg(long, value_enum)]
+    pub marker: Option<CanvasMarker>,
+
+    /// Name of a built-in theme or path to a theme file
+    #[arg(long, value_name = "THEME", env = "HILLCLIMB_THEME")]
+    pub theme: Option<String>,
+
+    /// Settings file
+    #[arg(long, value_name = "FILE", env = "HILLCLIMB_SETTINGS")]
+    pub settings: Option<PathBuf>,
+
+    /// Draw with ASCII characters only
+    #[arg(long)]
+    pub ascii: bool,
+
+    /// Use colors that are distinguishable with color vision deficiencies
+    #[arg(long)]
+    pub colorblind: bool,
+
+    /// Save the inputs of each run to a replay file when it ends
+    #[arg(long, value_name = "FILE", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Play a replay file back in its own level and car
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["level", "seed", "vehicle", "tick_rate"]
+    )]
+    pub replay: Option<PathBuf>,
+
+    /// Race against the ghost of a replay file, in its level and car
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["level", "seed", "vehicle", "tick_rate", "replay", "edit", "save"]
+    )]
+    pub ghost: Option<PathBuf>,
+
+    /// Open a level file in the editor, starting a new level if it doesn't
+    /// exist yet
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["level", "seed", "replay", "save"]
+    )]
+    pub edit: Option<PathBuf>,
+
+    /// Quicksave to a snapshot file, save to it on exit and resume from it
+    /// on start
+    #[arg(long, value_name = "FILE", conflicts_with = "replay")]
+    pub save: Option<PathBuf>,
+
+    /// Write the state of every physics step to a .csv or .jsonl file
+    #[arg(long, value_name = "FILE")]
+    pub telemetry: Option<PathBuf>,
+
+    /// Telemetry rows per simulated second instead of one per step, from 1
+    /// to 1000
+    #[arg(long, value_name = "HZ", requires = "telemetry")]
+    pub telemetry_rate: Option<f64>,
+
+    /// Continue telemetry in a new numbered file once one reaches this size
+    #[arg(long, value_name = "BYTES", requires = "telemetry")]
+    pub telemetry_max_bytes: Option<u64>,
+
+    /// Simulate without a terminal as fast as possible and print the result
+    /// as JSON, the same as hillclimb-sim
+    #[arg(long, conflicts_with_all = ["record", "edit", "save", "ghost"])]
+    pub headless: bool,
+
+    /// Script file with the inputs to apply in headless mode
+    #[arg(
+        long,
+        value_name = "FILE",
+        requires = "headless",
+        conflicts_with = "replay"
+    )]
+    pub script: Option<PathBuf>,
+
+    /// Simulated seconds after which a headless run is stopped
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value_t = sim::DEFAULT_DURATION,
+        value_parser = parse_duration,
+        requires = "headless"
+    )]
+    pub duration: f64,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum CanvasMarker {
+    Dot,
+    Block,
+    Bar,
+    Braille,
+    HalfBlock,
+}
+
+impl From<CanvasMarker> for Marker {
+    fn from(marker: CanvasMarker) -> Marker {
+        match marker {
+            CanvasMarker::Dot => Marker::Dot,
+            CanvasMarker::Block => Marker::Block,
+            CanvasMarker::Bar => Marker::Bar,
+            CanvasMarker::Braille => Marker::Braille,
+            CanvasMarker::HalfBlock => Marker::HalfBlock,
+        }
+    }
+}
+
+fn parse_rate(value: &str) -> Result<f64, String> {
+    let rate: f64 = value
+        .parse()
+        .map_err(|_| format!("{value} is not a number"))?;
+    if !(RATE_RANGE.0..=RATE_RANGE.1).contains(&rate) {
+        return Err(format!(
+            "must be between {} and {} Hz",
+            RATE_RANGE.0, RATE_RANGE.1
+        ));
+    }
+    Ok(rate)
+}
+
+fn parse_duration(value: &str) -> Result<f64, String> {
+    let duration: f64 = value
+        .parse()
+        .map_err(|_| format!("{value} is not a number"))?;
+    if !duration.is_finite() || duration <= 0.0 {
+        return Err("must be a positive number of seconds".to_string());
+    }
+    Ok(duration)
+}
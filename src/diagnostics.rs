@@ -0,0 +1,80 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+// Number of recent samples kept for averages and percentiles
+const TIMING_SAMPLES: usize = 240;
+// Window over which rates are measured
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Rolling window of how long an operation took
+#[derive(Default)]
+pub struct Timings {
+    samples: VecDeque<Duration>,
+}
+
+impl Timings {
+    pub fn record(&mut self, duration: Duration) {
+        if self.samples.len() == TIMING_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(duration);
+    }
+
+    pub fn average(&self) -> Duration {
+        if self.samples.is_empty() {
+            return Duration::ZERO;
+        }
+        self.samples.iter().sum::<Duration>() / self.samples.len() as u32
+    }
+
+    /// Duration that `fraction` of the recent samples didn't exceed
+    pub fn percentile(&self, fraction: f64) -> Duration {
+        let mut sorted: Vec<Duration> = self.samples.iter().copied().collect();
+        sorted.sort_unstable();
+        let index = ((sorted.len() as f64 * fraction).ceil() as usize).saturating_sub(1);
+        sorted.get(index).copied().unwrap_or_default()
+    }
+}
+
+/// Counts occurrences over the last second
+#[derive(Default)]
+pub struct RateCounter {
+    events: VecDeque<Instant>,
+}
+
+impl RateCounter {
+    pub fn record(&mut self, now: Instant) {
+        self.events.push_back(now);
+        while self
+            .events
+            .front()
+            .is_some_and(|event| now.duration_since(*event) > RATE_WINDOW)
+        {
+            self.events.pop_front();
+        }
+    }
+
+    /// Occurrences per second over the second before `now`, which drops to
+    /// zero when nothing is recorded, such as while paused
+    pub fn rate(&self, now: Instant) -> f64 {
+        let recent = self
+            .events
+            .iter()
+            .filter(|event| now.duration_since(**event) <= RATE_WINDOW)
+            .count();
+        recent as f64 / RATE_WINDOW.as_secs_f64()
+    }
+}
+
+/// Measurements of how well the game keeps up with its target rates
+#[derive(Default)]
+pub struct Diagnostics {
+    pub frames: RateCounter,
+    pub steps: RateCounter,
+    pub step_time: Timings,
+    pub draw_time: Timings,
+    /// Events waiting in the `Tui` channel when last checked
+    pub queue_depth: usize,
+}
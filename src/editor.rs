@@ -0,0 +1,271 @@
+use std::path::PathBuf;
+
+use color_eyre::eyre::Result;
+
+use crate::car::Car;
+use crate::game::{Game, PickupKind};
+use crate::level::{Level, PickupPlacement, SurfaceSpan};
+use crate::terrain::Terrain;
+
+// Distance from the cursor within which things can be picked or deleted
+const REACH: f64 = 12.0;
+// Every this many points of a generated terrain are kept as control points
+const CONTROL_POINT_STEP: usize = 5;
+// Closest two terrain points can be dragged together
+const MIN_POINT_GAP: f64 = 1.0;
+
+/// Something in the level that can be deleted, by index
+#[derive(Clone, Copy)]
+enum Item {
+    Point(usize),
+    Pickup(usize),
+    Checkpoint(usize),
+    Obstacle(usize),
+}
+
+/// A level being edited and the cursor used to change it
+pub struct Editor {
+    pub level: Level,
+    /// World position of the cursor
+    pub cursor: (f64, f64),
+    /// Index of the terrain point following the cursor
+    pub dragging: Option<usize>,
+    pub path: PathBuf,
+    /// Whether there are changes that haven't been saved
+    pub modified: bool,
+    /// Outcome of the last save or failed test drive
+    pub message: Option<String>,
+}
+
+impl Editor {
+    /// Edits `level`, saving it to `path`. The terrain, surfaces, gravity
+    /// zones, checkpoints and pickups the level leaves to be generated are
+    /// turned into placed ones first so they can be changed.
+    pub fn new(mut level: Level, path: PathBuf) -> Editor {
+        let game = Game::new(Car::default(), level.clone());
+        if level.is_generated() {
+            level.surfaces = level.surface_spans();
+            level.gravity_zones = level.gravity_zones();
+            let terrain = game.get_terrain();
+            level.terrain = terrain.points().step_by(CONTROL_POINT_STEP).collect();
+            if level
+                .terrain
+                .last()
+                .is_some_and(|(x, _)| *x < terrain.end_x())
+            {
+                level
+                    .terrain
+                    .push((terrain.end_x(), terrain.height_at(terrain.end_x())));
+            }
+        }
+        level
+            .checkpoints
+            .get_or_insert_with(|| game.get_checkpoints().to_vec());
+        level.pickups.get_or_insert_with(|| {
+            game.get_pickups()
+                .iter()
+                .map(|pickup| PickupPlacement {
+                    x: pickup.x,
+                    y: None,
+                    kind: pickup.kind,
+                })
+                .collect()
+        });
+
+        let cursor = (level.spawn, game.get_terrain().height_at(level.spawn));
+        Editor {
+            level,
+            cursor,
+            dragging: None,
+            path,
+            modified: false,
+            message: None,
+        }
+    }
+
+    /// Moves the cursor, and the terrain point being dragged along with it.
+    /// Returns whether the level changed.
+    pub fn move_cursor(&mut self, dx: f64, dy: f64) -> bool {
+        self.cursor.0 += dx;
+        self.cursor.1 += dy;
+        let Some(index) = self.dragging else {
+            return false;
+        };
+
+        // Points can't be dragged past their neighbours
+        let points = &mut self.level.terrain;
+        let low = index.checked_sub(1).map_or(f64::NEG_INFINITY, |previous| {
+            points[previous].0 + MIN_POINT_GAP
+        });
+        let high = points
+            .get(index + 1)
+            .map_or(f64::INFINITY, |next| next.0 - MIN_POINT_GAP);
+        self.cursor.0 = self.cursor.0.clamp(low, high);
+        points[index] = self.cursor;
+        self.modified = true;
+        true
+    }
+
+    /// Adds a terrain point at the cursor, or moves the point already at
+    /// the cursor's x up or down to it
+    pub fn add_point(&mut self) -> bool {
+        let points = &mut self.level.terrain;
+        let index = points.partition_point(|(x, _)| *x < self.cursor.0);
+        match points.get_mut(index) {
+            Some(point) if point.0 == self.cursor.0 => point.1 = self.cursor.1,
+            _ => points.insert(index, self.cursor),
+        }
+        self.modified = true;
+        true
+    }
+
+    /// Picks up the terrain point nearest the cursor to drag it, or drops
+    /// the one being dragged
+    pub fn toggle_drag(&mut self) {
+        if self.dragging.take().is_some() {
+            return;
+        }
+        if let Some((Item::Point(index), _)) = self
+            .items()
+            .into_iter()
+            .filter(|(item, _)| matches!(item, Item::Point(_)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .filter(|(_, distance)| *distance <= REACH)
+        {
+            self.dragging = Some(index);
+            self.cursor = self.level.terrain[index];
+        }
+    }
+
+    /// Deletes whatever is nearest the cursor. The terrain keeps at least
+    /// its two end points.
+    pub fn delete(&mut self) -> bool {
+        let Some((item, _)) = self
+            .items()
+            .into_iter()
+            .filter(|(item, _)| !matches!(item, Item::Point(_)) || self.level.terrain.len() > 2)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .filter(|(_, distance)| *distance <= REACH)
+        else {
+            return false;
+        };
+
+        match item {
+            Item::Point(index) => {
+                self.level.terrain.remove(index);
+            }
+            Item::Pickup(index) => {
+                self.level.pickups.get_or_insert_default().remove(index);
+            }
+            Item::Checkpoint(index) => {
+                self.level.checkpoints.get_or_insert_default().remove(index);
+            }
+            Item::Obstacle(index) => {
+                self.level.obstacles.remove(index);
+            }
+        }
+        self.dragging = None;
+        self.modified = true;
+        true
+    }
+
+    /// Places a pickup floating at the cursor
+    pub fn place_pickup(&mut self, kind: PickupKind) -> bool {
+        let pickups = self.level.pickups.get_or_insert_default();
+        pickups.push(PickupPlacement {
+            x: self.cursor.0,
+            y: Some(self.cursor.1),
+            kind,
+        });
+        pickups.sort_by(|a, b| a.x.total_cmp(&b.x));
+        self.modified = true;
+        true
+    }
+
+    /// Places a checkpoint at the cursor's x
+    pub fn place_checkpoint(&mut self) -> bool {
+        let checkpoints = self.level.checkpoints.get_or_insert_default();
+        checkpoints.push(self.cursor.0);
+        checkpoints.sort_by(f64::total_cmp);
+        self.modified = true;
+        true
+    }
+
+    /// Changes the surface from the cursor onwards to the next one. Changes
+    /// the stretch starting near the cursor, or starts a new one there.
+    pub fn cycle_surface(&mut self) -> bool {
+        let x = self.cursor.0;
+        let next = self.level.surface_at(x).next();
+        let surfaces = &mut self.level.surfaces;
+        match surfaces
+            .iter_mut()
+            .find(|span| (span.from - x).abs() <= REACH)
+        {
+            Some(span) => span.surface = span.surface.next(),
+            None => {
+                let index = surfaces.partition_point(|span| span.from < x);
+                surfaces.insert(
+                    index,
+                    SurfaceSpan {
+                        from: x,
+                        surface: next,
+                    },
+                );
+            }
+        }
+        self.modified = true;
+        true
+    }
+
+    /// Writes the level to its file if it's playable
+    pub fn save(&mut self) -> Result<()> {
+        self.level.validate()?;
+        self.level.save(&self.path)?;
+        self.modified = false;
+        Ok(())
+    }
+
+    // Everything that can be deleted, with its distance from the cursor
+    fn items(&self) -> Vec<(Item, f64)> {
+        let terrain = Terrain::from_points(self.level.terrain.clone());
+        let distance = |x: f64, y: f64| (x - self.cursor.0).hypot(y - self.cursor.1);
+
+        let mut items: Vec<(Item, f64)> = self
+            .level
+            .terrain
+            .iter()
+            .enumerate()
+            .map(|(index, (x, y))| (Item::Point(index), distance(*x, *y)))
+            .collect();
+        items.extend(
+            self.level
+                .pickups
+                .iter()
+                .flatten()
+                .enumerate()
+                .map(|(index, pickup)| {
+                    let y = pickup.y.unwrap_or(terrain.height_at(pickup.x));
+                    (Item::Pickup(index), distance(pickup.x, y))
+                }),
+        );
+        items.extend(
+            self.level
+                .checkpoints
+                .iter()
+                .flatten()
+                .enumerate()
+                .map(|(index, x)| (Item::Checkpoint(index), distance(*x, terrain.height_at(*x)))),
+        );
+        items.extend(
+            self.level
+                .obstacles
+                .iter()
+                .enumerate()
+                .map(|(index, obstacle)| {
+                    let y = terrain.height_at(obstacle.x);
+                    (Item::Obstacle(index), distance(obstacle.x, y))
+                }),
+        );
+        items
+    }
+}
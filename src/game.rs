@@ -1,45 +1,159 @@
 use nalgebra::SVector;
 // use rapier2d_f64::counters::Timer;
+use rapier2d_f64::crossbeam::channel::{self, Receiver};
 use rapier2d_f64::dynamics::{RevoluteJointBuilder, RigidBodyHandle};
 use rapier2d_f64::na::point;
 use rapier2d_f64::prelude::nalgebra;
 use rapier2d_f64::prelude::{
-    vector, CCDSolver, ColliderBuilder, ColliderSet, DefaultBroadPhase, ImpulseJointSet,
-    IntegrationParameters, IslandManager, MultibodyJointSet, NarrowPhase, PhysicsPipeline,
-    QueryPipeline, RigidBodyBuilder, RigidBodySet,
+    vector, ActiveEvents, CCDSolver, ChannelEventCollector, ColliderBuilder, ColliderHandle,
+    ColliderSet, ContactForceEvent, DefaultBroadPhase, ImpulseJointSet, IntegrationParameters,
+    IslandManager, MultibodyJointSet, NarrowPhase, PhysicsPipeline, QueryPipeline,
+    RigidBodyBuilder, RigidBodySet,
 };
 
-pub struct Car {
-    pub rear_wheel_radius: f64,
-    pub front_wheel_radius: f64,
-    pub body_half_width: f64,
-    pub body_half_height: f64,
+use serde::{Deserialize, Serialize};
+
+use crate::background::{self, BackgroundLayer};
+use crate::car::Car;
+use crate::input::{Input, TimedInput, THROTTLE_STEP};
+use crate::level::{self, GravityZone, Level, Obstacle, EARTH_GRAVITY};
+use crate::snapshot::Snapshot;
+use crate::surface::Surface;
+use crate::terrain::Terrain;
+
+/// Whether the physics were built to give bit for bit the same results on
+/// every platform, see the `deterministic` cargo feature
+pub const DETERMINISTIC: bool = cfg!(feature = "deterministic");
+
+/// Maximum torque the rear wheel motor can apply in either direction
+pub const MAX_TORQUE: f64 = 25000.0;
+/// Fuel in a full tank, burned while the throttle is open
+pub const FUEL_CAPACITY: f64 = 100.0;
+// Fuel burned per second at full throttle
+const FUEL_BURN_RATE: f64 = 2.0;
+
+const CHECKPOINT_SPACING: f64 = 1000.0;
+const FUEL_SPACING: f64 = 200.0;
+const COIN_SPACING: f64 = 50.0;
+// Height of pickups above the ground
+const PICKUP_HEIGHT: f64 = 8.0;
+// Height above the ground the car is dropped from at the start
+const SPAWN_HEIGHT: f64 = 10.0;
+// Distance from the car body's center within which pickups are collected
+const PICKUP_REACH: f64 = 20.0;
+// Contact force above which a collision with the car counts as an impact
+const IMPACT_FORCE_THRESHOLD: f64 = 30000.0;
+// Speed below which a car with an empty tank counts as stopped
+const STOPPED_SPEED: f64 = 0.5;
+// Distance before the end of the terrain where the finish line is
+const FINISH_MARGIN: f64 = 20.0;
+// FNV-1a parameters for the state hash
+const FNV_OFFSET_BASIS: u64 = 0xCBF2_9CE4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01B3;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PickupKind {
+    /// Refills the tank
+    Fuel,
+    /// Counts towards [`Stats::coins`]
+    Coin,
+}
+
+/// Item floating above the track that the car collects by driving through it
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Pickup {
+    pub x: f64,
+    pub y: f64,
+    pub kind: PickupKind,
+    pub collected: bool,
+}
+
+/// Stretch of ground with a single surface
+#[derive(Clone, Copy)]
+pub struct SurfaceStretch {
+    pub from: f64,
+    pub to: f64,
+    pub surface: Surface,
+}
+
+/// Running statistics for the current attempt
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Stats {
+    /// Simulated seconds since the start of the run
+    pub time: f64,
+    /// Horizontal distance from the spawn point
+    pub distance: f64,
+    /// Farthest distance reached this run
+    pub max_distance: f64,
+    /// Seconds since both wheels left the ground, zero while grounded
+    pub airtime: f64,
+    /// Longest single jump this run
+    pub max_airtime: f64,
+    /// Seconds in the air this run, weighted by gravity against the earth's
+    /// so floating through low gravity doesn't count for more
+    pub air_score: f64,
+    /// Coins collected this run
+    pub coins: u32,
+    /// Times the run was rewound, which keeps it from counting as a record
+    pub rewinds: u32,
 }
 
-impl Car {
-    pub fn new(
-        rear_wheel_radius: f64,
-        front_wheel_radius: f64,
-        body_half_width: f64,
-        body_half_height: f64,
-    ) -> Car {
-        Car {
-            rear_wheel_radius,
-            front_wheel_radius,
-            body_half_width,
-            body_half_height,
+/// Why the car can't go on with the run
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Crash {
+    /// The car body is upside down on the ground
+    Flipped,
+    /// The tank is empty and the car has come to a stop
+    OutOfFuel,
+}
+
+impl Crash {
+    pub fn name(self) -> &'static str {
+        match self {
+            Crash::Flipped => "flipped",
+            Crash::OutOfFuel => "out of fuel",
         }
     }
 }
 
+/// A hard collision between the car and the ground during the last step
+pub struct Impact {
+    pub x: f64,
+    pub y: f64,
+    /// Magnitude of the contact force
+    pub force: f64,
+}
+
+/// The physics world of a single run: the track, the car and its pickups.
+/// Advance it with [`Game::step_physics`] and steer it with [`Game::apply_input`].
 pub struct Game {
     car: Car,
+    level: Level,
+    terrain: Terrain,
+    surfaces: Vec<SurfaceStretch>,
+    gravity_zones: Vec<GravityZone>,
+    background: Vec<BackgroundLayer>,
+    pickups: Vec<Pickup>,
+    checkpoints: Vec<f64>,
+    finish_x: f64,
+    fuel: f64,
+    // Physics steps taken since the start of the run
+    tick: u64,
+    // Inputs applied so far this run
+    inputs: Vec<TimedInput>,
+    stats: Stats,
+    spawn_x: f64,
     gravity: SVector<f64, 2>,
     rigid_body_set: RigidBodySet,
     collider_set: ColliderSet,
     car_body_handle: RigidBodyHandle,
     front_wheel_handle: RigidBodyHandle,
     rear_wheel_handle: RigidBodyHandle,
+    car_body_collider_handle: ColliderHandle,
+    front_wheel_collider_handle: ColliderHandle,
+    rear_wheel_collider_handle: ColliderHandle,
     integration_parameters: IntegrationParameters,
     physics_pipeline: PhysicsPipeline,
     island_manager: IslandManager,
@@ -51,58 +165,107 @@ pub struct Game {
     query_pipeline: QueryPipeline,
     // pub timer: Timer,
     // physics_hooks: dyn PhysicsHooks,
-    // event_handler: &EventHandler,
+    event_handler: ChannelEventCollector,
+    contact_force_receiver: Receiver<ContactForceEvent>,
+    impacts: Vec<Impact>,
 }
 
 impl Game {
-    pub fn new(car: Car) -> Game {
+    /// Builds the level's track and places `car` at the spawn point
+    pub fn new(car: Car, level: Level) -> Game {
         let mut rigid_body_set = RigidBodySet::new();
         let mut collider_set = ColliderSet::new();
         let mut impulse_joint_set = ImpulseJointSet::new();
         let multibody_joint_set = MultibodyJointSet::new();
 
-        // create the ground
-        // TODO: convert to procedural terrain
-        let collider = ColliderBuilder::cuboid(100.0, 1.0)
-            // .collision_groups(InteractionGroups::new(Group::GROUP_2, Group::GROUP_1))
-            .build();
-        collider_set.insert(collider);
+        let terrain = level.terrain();
+        let surfaces = surface_stretches(&terrain, &level);
+        build_ground(&terrain, &surfaces, &mut collider_set);
+        for obstacle in &level.obstacles {
+            let (half_width, half_height) = (obstacle.width / 2.0, obstacle.height / 2.0);
+            let collider = ColliderBuilder::cuboid(half_width, half_height)
+                .translation(vector![
+                    obstacle.x,
+                    terrain.height_at(obstacle.x) + half_height
+                ])
+                .build();
+            collider_set.insert(collider);
+        }
+        let background = background::generate(level.seed, level.background_layers());
+        let pickups = match &level.pickups {
+            Some(placements) => placements
+                .iter()
+                .map(|placement| Pickup {
+                    x: placement.x,
+                    y: placement
+                        .y
+                        .unwrap_or(terrain.height_at(placement.x) + PICKUP_HEIGHT),
+                    kind: placement.kind,
+                    collected: false,
+                })
+                .collect(),
+            None => place_pickups(&terrain),
+        };
+        let checkpoints = match &level.checkpoints {
+            Some(checkpoints) => {
+                let mut checkpoints = checkpoints.clone();
+                checkpoints.sort_by(f64::total_cmp);
+                checkpoints
+            }
+            None => (1..)
+                .map(|i| i as f64 * CHECKPOINT_SPACING)
+                .take_while(|x| *x < terrain.end_x())
+                .collect(),
+        };
+        let finish_x = level.finish.unwrap_or(terrain.end_x() - FINISH_MARGIN);
 
+        let spawn_x = level.spawn;
+        let spawn_y = terrain.height_at(spawn_x) + SPAWN_HEIGHT;
         let car_body = RigidBodyBuilder::dynamic()
-            .translation(vector![0.0, 10.0])
+            .translation(vector![spawn_x, spawn_y])
             .linear_damping(0.5)
             .build();
         let car_body_collider = ColliderBuilder::cuboid(car.body_half_width, car.body_half_height)
             // .collision_groups(InteractionGroups::new(Group::GROUP_1, Group::GROUP_2))
+            .active_events(ActiveEvents::CONTACT_FORCE_EVENTS)
+            .contact_force_event_threshold(IMPACT_FORCE_THRESHOLD)
             .build();
         let car_body_handle = rigid_body_set.insert(car_body);
-        collider_set.insert_with_parent(car_body_collider, car_body_handle, &mut rigid_body_set);
+        let car_body_collider_handle = collider_set.insert_with_parent(
+            car_body_collider,
+            car_body_handle,
+            &mut rigid_body_set,
+        );
 
         let rear_wheel = RigidBodyBuilder::dynamic()
-            .translation(vector![0.0, 10.0])
+            .translation(vector![spawn_x, spawn_y])
             .angular_damping(1.0)
             .build();
         let rear_wheel_collider = ColliderBuilder::ball(car.rear_wheel_radius)
             .restitution(0.7)
             // .collision_groups(InteractionGroups::new(Group::GROUP_1, Group::GROUP_2))
+            .active_events(ActiveEvents::CONTACT_FORCE_EVENTS)
+            .contact_force_event_threshold(IMPACT_FORCE_THRESHOLD)
             .build();
         let rear_wheel_handle = rigid_body_set.insert(rear_wheel);
-        collider_set.insert_with_parent(
+        let rear_wheel_collider_handle = collider_set.insert_with_parent(
             rear_wheel_collider,
             rear_wheel_handle,
             &mut rigid_body_set,
         );
 
         let front_wheel = RigidBodyBuilder::dynamic()
-            .translation(vector![car.body_half_width, 10.0])
+            .translation(vector![spawn_x + car.body_half_width, spawn_y])
             .angular_damping(1.0)
             .build();
         let front_wheel_collider = ColliderBuilder::ball(car.front_wheel_radius)
             // .restitution(0.7)
             // .collision_groups(InteractionGroups::new(Group::GROUP_1, Group::GROUP_2))
+            .active_events(ActiveEvents::CONTACT_FORCE_EVENTS)
+            .contact_force_event_threshold(IMPACT_FORCE_THRESHOLD)
             .build();
         let front_wheel_handle = rigid_body_set.insert(front_wheel);
-        collider_set.insert_with_parent(
+        let front_wheel_collider_handle = collider_set.insert_with_parent(
             front_wheel_collider,
             front_wheel_handle,
             &mut rigid_body_set,
@@ -133,17 +296,43 @@ impl Game {
         //     .insert(car_body_handle, front_wheel_handle, front_wheel_joint, true)
         //     .unwrap();
 
+        // Collision start/stop events aren't used, only contact forces
+        let (collision_sender, _) = channel::unbounded();
+        let (contact_force_sender, contact_force_receiver) = channel::unbounded();
+        let event_handler = ChannelEventCollector::new(collision_sender, contact_force_sender);
+
         // let mut timer = Timer::new();
         // timer.start();
 
+        let gravity_zones = level.gravity_zones();
+        let gravity = vector![
+            0.0,
+            -level::gravity_at(level.gravity, &gravity_zones, spawn_x)
+        ];
         Game {
             car,
-            gravity: vector![0.0, -9.81],
+            level,
+            terrain,
+            surfaces,
+            gravity_zones,
+            background,
+            pickups,
+            checkpoints,
+            finish_x,
+            fuel: FUEL_CAPACITY,
+            tick: 0,
+            inputs: Vec::new(),
+            stats: Stats::default(),
+            spawn_x,
+            gravity,
             rigid_body_set,
             collider_set,
             car_body_handle,
             front_wheel_handle,
             rear_wheel_handle,
+            car_body_collider_handle,
+            front_wheel_collider_handle,
+            rear_wheel_collider_handle,
             integration_parameters: IntegrationParameters::default(),
             physics_pipeline: PhysicsPipeline::new(),
             island_manager: IslandManager::new(),
@@ -155,17 +344,103 @@ impl Game {
             query_pipeline: QueryPipeline::new(),
             // timer,
             // physics_hooks: (),
-            // event_handler: (),
+            event_handler,
+            contact_force_receiver,
+            impacts: Vec::new(),
         }
 
         /* Create other structures necessary for the simulation. */
     }
 
+    /// Captures everything needed to carry on from this point later
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            car: self.car,
+            level: self.level.clone(),
+            pickups: self.pickups.clone(),
+            fuel: self.fuel,
+            tick: self.tick,
+            stats: self.stats,
+            inputs: self.inputs.clone(),
+            gravity: self.gravity,
+            integration_parameters: self.integration_parameters,
+            rigid_body_set: self.rigid_body_set.clone(),
+            collider_set: self.collider_set.clone(),
+            impulse_joint_set: self.impulse_joint_set.clone(),
+            multibody_joint_set: self.multibody_joint_set.clone(),
+            island_manager: self.island_manager.clone(),
+            broad_phase: self.broad_phase.clone(),
+            narrow_phase: self.narrow_phase.clone(),
+            ccd_solver: self.ccd_solver.clone(),
+            car_body_handle: self.car_body_handle,
+            front_wheel_handle: self.front_wheel_handle,
+            rear_wheel_handle: self.rear_wheel_handle,
+            car_body_collider_handle: self.car_body_collider_handle,
+            front_wheel_collider_handle: self.front_wheel_collider_handle,
+            rear_wheel_collider_handle: self.rear_wheel_collider_handle,
+        }
+    }
+
+    /// Rebuilds the game exactly as it was when `snapshot` was taken
+    pub fn restore(snapshot: Snapshot) -> Game {
+        // The track and scenery are regenerated from the level, everything
+        // that changes during a run comes from the snapshot
+        let mut game = Game::new(snapshot.car, snapshot.level);
+        game.pickups = snapshot.pickups;
+        game.fuel = snapshot.fuel;
+        game.tick = snapshot.tick;
+        game.stats = snapshot.stats;
+        game.inputs = snapshot.inputs;
+        game.gravity = snapshot.gravity;
+        game.integration_parameters = snapshot.integration_parameters;
+        game.rigid_body_set = snapshot.rigid_body_set;
+        game.collider_set = snapshot.collider_set;
+        game.impulse_joint_set = snapshot.impulse_joint_set;
+        game.multibody_joint_set = snapshot.multibody_joint_set;
+        game.island_manager = snapshot.island_manager;
+        game.broad_phase = snapshot.broad_phase;
+        game.narrow_phase = snapshot.narrow_phase;
+        game.ccd_solver = snapshot.ccd_solver;
+        game.car_body_handle = snapshot.car_body_handle;
+        game.front_wheel_handle = snapshot.front_wheel_handle;
+        game.rear_wheel_handle = snapshot.rear_wheel_handle;
+        game.car_body_collider_handle = snapshot.car_body_collider_handle;
+        game.front_wheel_collider_handle = snapshot.front_wheel_collider_handle;
+        game.rear_wheel_collider_handle = snapshot.rear_wheel_collider_handle;
+        game
+    }
+
+    /// Goes back to `snapshot`, taken earlier in the run, and counts the
+    /// rewind in the stats
+    pub fn rewind(&mut self, snapshot: Snapshot) {
+        let rewinds = self.stats.rewinds + 1;
+        *self = Game::restore(snapshot);
+        self.stats.rewinds = rewinds;
+    }
+
+    /// Sets the simulated seconds that each physics step advances
+    pub fn with_time_step(mut self, dt: f64) -> Self {
+        self.integration_parameters.dt = dt;
+        self
+    }
+
+    /// Counts `rewinds` earlier rewinds, for a run played back from the
+    /// recording of a rewound run
+    pub(crate) fn with_rewinds(mut self, rewinds: u32) -> Self {
+        self.stats.rewinds = rewinds;
+        self
+    }
+
     // TODO: the physics loop needs to be detached from the game loop
     // as rendering is done slowly
+    /// Advances the simulation by one time step
     pub fn step_physics(&mut self) {
         // self.timer.pause();
         // self.integration_parameters.dt = self.timer.time();
+        // Gravity changes along the track, the car feels whatever is under it
+        let car_x = self.rigid_body_set[self.car_body_handle].translation().x;
+        let gravity = level::gravity_at(self.level.gravity, &self.gravity_zones, car_x);
+        self.gravity = vector![0.0, -gravity];
         self.physics_pipeline.step(
             &self.gravity,
             &self.integration_parameters,
@@ -179,9 +454,16 @@ impl Game {
             &mut self.ccd_solver,
             Some(&mut self.query_pipeline),
             &(),
-            &(),
+            &self.event_handler,
         );
 
+        self.tick += 1;
+        self.apply_rolling_resistance();
+        self.record_impacts();
+        self.update_stats();
+        self.collect_pickups();
+        self.burn_fuel();
+
         // self.reset_torque();
         // self.timer.resume();
 
@@ -189,14 +471,317 @@ impl Game {
         // println!("Ball altitude: {}", ball_body.translation().y);
     }
 
+    // Slows the spin of wheels rolling on surfaces that hold them back
+    fn apply_rolling_resistance(&mut self) {
+        let dt = self.integration_parameters.dt;
+        for (wheel_handle, collider_handle) in [
+            (self.front_wheel_handle, self.front_wheel_collider_handle),
+            (self.rear_wheel_handle, self.rear_wheel_collider_handle),
+        ] {
+            let resistance = self
+                .get_contact_surface(collider_handle)
+                .map_or(0.0, Surface::rolling_resistance);
+            if resistance == 0.0 {
+                continue;
+            }
+
+            let wheel = &mut self.rigid_body_set[wheel_handle];
+            let angvel = wheel.angvel() * (1.0 - resistance * dt).max(0.0);
+            wheel.set_angvel(angvel, true);
+        }
+    }
+
+    fn record_impacts(&mut self) {
+        self.impacts.clear();
+        while let Ok(event) = self.contact_force_receiver.try_recv() {
+            // Place the impact at the first contact point of the pair
+            let Some(point) = self
+                .narrow_phase
+                .contact_pair(event.collider1, event.collider2)
+                .and_then(|pair| pair.manifolds.first())
+                .and_then(|manifold| manifold.data.solver_contacts.first())
+                .map(|contact| contact.point)
+            else {
+                continue;
+            };
+
+            self.impacts.push(Impact {
+                x: point.x,
+                y: point.y,
+                force: event.total_force_magnitude,
+            });
+        }
+    }
+
+    fn update_stats(&mut self) {
+        let dt = self.integration_parameters.dt;
+        let airborne = self.is_airborne();
+        let gravity = self.get_gravity();
+        let stats = &mut self.stats;
+        stats.time += dt;
+        stats.distance = self.rigid_body_set[self.car_body_handle].translation().x - self.spawn_x;
+        stats.max_distance = stats.max_distance.max(stats.distance);
+
+        if airborne {
+            stats.airtime += dt;
+            stats.max_airtime = stats.max_airtime.max(stats.airtime);
+            stats.air_score += dt * gravity / EARTH_GRAVITY;
+        } else {
+            stats.airtime = 0.0;
+        }
+    }
+
+    fn collect_pickups(&mut self) {
+        let car_position = *self.rigid_body_set[self.car_body_handle].translation();
+        for pickup in self.pickups.iter_mut().filter(|pickup| !pickup.collected) {
+            if (pickup.x - car_position.x).hypot(pickup.y - car_position.y) > PICKUP_REACH {
+                continue;
+            }
+
+            pickup.collected = true;
+            match pickup.kind {
+                PickupKind::Fuel => self.fuel = FUEL_CAPACITY,
+                PickupKind::Coin => self.stats.coins += 1,
+            }
+        }
+    }
+
+    fn burn_fuel(&mut self) {
+        let dt = self.integration_parameters.dt;
+        self.fuel = (self.fuel - self.get_throttle().abs() * FUEL_BURN_RATE * dt).max(0.0);
+        if self.fuel == 0.0 {
+            self.rigid_body_set[self.rear_wheel_handle].reset_torques(true);
+        }
+    }
+
     pub fn get_car(&self) -> &Car {
         &self.car
     }
 
+    pub fn get_level(&self) -> &Level {
+        &self.level
+    }
+
+    pub fn get_terrain(&self) -> &Terrain {
+        &self.terrain
+    }
+
+    /// Stretches of ground with their surfaces, from left to right
+    pub fn get_surfaces(&self) -> &[SurfaceStretch] {
+        &self.surfaces
+    }
+
+    /// Where the gravity changes along the track
+    pub fn get_gravity_zones(&self) -> &[GravityZone] {
+        &self.gravity_zones
+    }
+
+    /// Downward acceleration the car is under
+    pub fn get_gravity(&self) -> f64 {
+        -self.gravity.y
+    }
+
+    /// Surface under the rear wheel, `None` while it's in the air
+    pub fn get_rear_wheel_surface(&self) -> Option<Surface> {
+        self.get_contact_surface(self.rear_wheel_collider_handle)
+    }
+
+    /// Scenery layers, ordered from farthest to nearest
+    pub fn get_background(&self) -> &[BackgroundLayer] {
+        &self.background
+    }
+
+    pub fn get_pickups(&self) -> &[Pickup] {
+        &self.pickups
+    }
+
+    /// X coordinates of the checkpoints along the track
+    pub fn get_checkpoints(&self) -> &[f64] {
+        &self.checkpoints
+    }
+
+    /// X coordinate of the car's starting position
+    pub fn get_spawn_x(&self) -> f64 {
+        self.spawn_x
+    }
+
+    /// X coordinate of the finish line
+    pub fn get_finish_x(&self) -> f64 {
+        self.finish_x
+    }
+
+    pub fn get_obstacles(&self) -> &[Obstacle] {
+        &self.level.obstacles
+    }
+
+    pub fn get_rigid_body_set(&self) -> &RigidBodySet {
+        &self.rigid_body_set
+    }
+
+    pub fn get_collider_set(&self) -> &ColliderSet {
+        &self.collider_set
+    }
+
+    pub fn get_impulse_joint_set(&self) -> &ImpulseJointSet {
+        &self.impulse_joint_set
+    }
+
+    pub fn get_narrow_phase(&self) -> &NarrowPhase {
+        &self.narrow_phase
+    }
+
+    /// Hard collisions that happened during the last physics step
+    pub fn get_impacts(&self) -> &[Impact] {
+        &self.impacts
+    }
+
+    /// Hash of the pose and velocity of every body, the fuel and the run's
+    /// stats. Two runs that hash the same after every step stayed identical,
+    /// which needs the same level, car, time step and inputs.
+    pub fn state_hash(&self) -> u64 {
+        let mut hash = FNV_OFFSET_BASIS;
+        let mut write = |value: f64| {
+            for byte in value.to_bits().to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
+        };
+
+        for (_, body) in self.rigid_body_set.iter() {
+            let (translation, rotation) = (body.translation(), body.rotation());
+            let (linvel, angvel) = (body.linvel(), body.angvel());
+            for value in [
+                translation.x,
+                translation.y,
+                rotation.re,
+                rotation.im,
+                linvel.x,
+                linvel.y,
+                angvel,
+            ] {
+                write(value);
+            }
+        }
+        let stats = &self.stats;
+        for value in [
+            self.fuel,
+            stats.time,
+            stats.max_distance,
+            stats.max_airtime,
+            stats.coins as f64,
+        ] {
+            write(value);
+        }
+
+        hash
+    }
+
+    /// Physics steps taken since the start of the run
+    pub fn get_tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Inputs applied so far this run, in order
+    pub fn get_inputs(&self) -> &[TimedInput] {
+        &self.inputs
+    }
+
+    /// Simulated seconds that each physics step advances
+    pub fn get_time_step(&self) -> f64 {
+        self.integration_parameters.dt
+    }
+
+    pub fn get_stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    pub fn get_fuel(&self) -> f64 {
+        self.fuel
+    }
+
+    /// Torque driving the rear wheel, positive forward and negative when
+    /// reversing
+    pub fn get_drive_torque(&self) -> f64 {
+        // Clockwise torque drives the car forward. Subtracted from zero so
+        // coasting gives 0 rather than -0.
+        0.0 - self.get_rear_wheel_torque()
+    }
+
+    /// Rear wheel torque as a fraction of [`MAX_TORQUE`], negative when reversing
+    pub fn get_throttle(&self) -> f64 {
+        self.get_drive_torque() / MAX_TORQUE
+    }
+
+    /// Speed of the car body in units per second
+    pub fn get_car_speed(&self) -> f64 {
+        self.rigid_body_set[self.car_body_handle].linvel().norm()
+    }
+
+    /// Velocity of the car body in units per second
+    pub fn get_car_velocity(&self) -> (f64, f64) {
+        let linvel = self.rigid_body_set[self.car_body_handle].linvel();
+        (linvel.x, linvel.y)
+    }
+
+    /// Angular velocity of the car body in radians per second, counterclockwise
+    pub fn get_car_angular_velocity(&self) -> f64 {
+        self.rigid_body_set[self.car_body_handle].angvel()
+    }
+
+    /// Angular velocity of the front wheel in radians per second, negative
+    /// while rolling forward
+    pub fn get_front_wheel_angular_velocity(&self) -> f64 {
+        self.rigid_body_set[self.front_wheel_handle].angvel()
+    }
+
+    /// Angular velocity of the rear wheel in radians per second, negative
+    /// while rolling forward
+    pub fn get_rear_wheel_angular_velocity(&self) -> f64 {
+        self.rigid_body_set[self.rear_wheel_handle].angvel()
+    }
+
+    /// Whether the car body itself touches the ground
+    pub fn is_car_body_grounded(&self) -> bool {
+        self.is_in_contact(self.car_body_collider_handle)
+    }
+
+    pub fn is_front_wheel_grounded(&self) -> bool {
+        self.is_in_contact(self.front_wheel_collider_handle)
+    }
+
+    pub fn is_rear_wheel_grounded(&self) -> bool {
+        self.is_in_contact(self.rear_wheel_collider_handle)
+    }
+
+    /// Why the run is over, if it is
+    pub fn get_crash(&self) -> Option<Crash> {
+        let upside_down = self.get_car_body_angle().cos() < 0.0;
+        if upside_down && self.is_in_contact(self.car_body_collider_handle) {
+            Some(Crash::Flipped)
+        } else if self.fuel == 0.0 && self.get_car_speed() < STOPPED_SPEED {
+            Some(Crash::OutOfFuel)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the car has crossed the finish line
+    pub fn is_finished(&self) -> bool {
+        self.get_car_body_x() >= self.finish_x
+    }
+
+    /// Whether neither wheel touches the ground
+    pub fn is_airborne(&self) -> bool {
+        !self.is_front_wheel_grounded() && !self.is_rear_wheel_grounded()
+    }
+
+    /// Motor torque on the rear wheel, in rapier's sign convention where
+    /// negative values drive the car forward
     pub fn get_rear_wheel_torque(&self) -> f64 {
         self.rigid_body_set[self.rear_wheel_handle].user_torque()
     }
 
+    /// Position of the car body's center
     pub fn get_car_body_x(&self) -> f64 {
         self.rigid_body_set[self.car_body_handle].translation().x
     }
@@ -205,6 +790,7 @@ impl Game {
         self.rigid_body_set[self.car_body_handle].translation().y
     }
 
+    /// Rotation of the car body in radians, counterclockwise
     pub fn get_car_body_angle(&self) -> f64 {
         self.rigid_body_set[self.car_body_handle].rotation().angle()
     }
@@ -225,11 +811,163 @@ impl Game {
         self.rigid_body_set[self.rear_wheel_handle].translation().y
     }
 
+    pub fn get_front_wheel_angle(&self) -> f64 {
+        self.rigid_body_set[self.front_wheel_handle]
+            .rotation()
+            .angle()
+    }
+
+    pub fn get_rear_wheel_angle(&self) -> f64 {
+        self.rigid_body_set[self.rear_wheel_handle]
+            .rotation()
+            .angle()
+    }
+
+    /// Difference between the front wheel's surface speed and its speed over
+    /// the ground, zero while the wheel is in the air.
+    pub fn get_front_wheel_slip(&self) -> f64 {
+        self.get_wheel_slip(
+            self.front_wheel_handle,
+            self.front_wheel_collider_handle,
+            self.car.front_wheel_radius,
+        )
+    }
+
+    /// Difference between the rear wheel's surface speed and its speed over
+    /// the ground, zero while the wheel is in the air.
+    pub fn get_rear_wheel_slip(&self) -> f64 {
+        self.get_wheel_slip(
+            self.rear_wheel_handle,
+            self.rear_wheel_collider_handle,
+            self.car.rear_wheel_radius,
+        )
+    }
+
+    fn get_wheel_slip(
+        &self,
+        wheel_handle: RigidBodyHandle,
+        collider_handle: ColliderHandle,
+        radius: f64,
+    ) -> f64 {
+        if !self.is_in_contact(collider_handle) {
+            return 0.0;
+        }
+
+        let wheel = &self.rigid_body_set[wheel_handle];
+        // A positive angular velocity rolls the wheel backwards
+        let surface_speed = -wheel.angvel() * radius;
+        let ground_speed = wheel.linvel().norm().copysign(wheel.linvel().x);
+        surface_speed - ground_speed
+    }
+
+    // Surface of the ground the collider touches, if it touches any
+    fn get_contact_surface(&self, collider_handle: ColliderHandle) -> Option<Surface> {
+        self.narrow_phase
+            .contact_pairs_with(collider_handle)
+            .filter(|pair| pair.has_any_active_contact)
+            .find_map(|pair| {
+                let other = if pair.collider1 == collider_handle {
+                    pair.collider2
+                } else {
+                    pair.collider1
+                };
+                Surface::from_user_data(self.collider_set[other].user_data)
+            })
+    }
+
+    fn is_in_contact(&self, collider_handle: ColliderHandle) -> bool {
+        self.narrow_phase
+            .contact_pairs_with(collider_handle)
+            .any(|pair| pair.has_any_active_contact)
+    }
+
+    /// Adds to the rear wheel's motor torque, positive values drive the car
+    /// forward (to the right)
     pub fn apply_torque(&mut self, torque: f64) {
-        self.rigid_body_set[self.rear_wheel_handle].add_torque(torque, true);
+        if self.fuel == 0.0 {
+            return;
+        }
+
+        let rear_wheel = &mut self.rigid_body_set[self.rear_wheel_handle];
+        let torque = (rear_wheel.user_torque() - torque).clamp(-MAX_TORQUE, MAX_TORQUE);
+        rear_wheel.reset_torques(true);
+        rear_wheel.add_torque(torque, true);
+    }
+
+    /// Applies `input` before the next physics step and keeps it for replays
+    pub fn apply_input(&mut self, input: Input) {
+        self.inputs.push(TimedInput {
+            tick: self.tick,
+            input,
+        });
+        match input {
+            Input::Accelerate => self.apply_torque(THROTTLE_STEP),
+            Input::Decelerate => self.apply_torque(-THROTTLE_STEP),
+        }
     }
 
     // pub fn reset_torque(&mut self) {
     // self.rigid_body_set[self.rear_wheel_handle].reset_torques(true);
     // }
 }
+
+// Splits the terrain where the level's surfaces change
+fn surface_stretches(terrain: &Terrain, level: &Level) -> Vec<SurfaceStretch> {
+    let (start, end) = (terrain.start_x(), terrain.end_x());
+    let mut starts = vec![(start, level.surface)];
+    starts.extend(
+        level
+            .surface_spans()
+            .iter()
+            .map(|span| (span.from, span.surface)),
+    );
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(index, (from, surface))| SurfaceStretch {
+            from: *from,
+            to: starts.get(index + 1).map_or(end, |next| next.0),
+            surface: *surface,
+        })
+        .filter(|stretch| stretch.to > stretch.from)
+        .collect()
+}
+
+// Adds the ground as one polyline per stretch, with the stretch's surface
+// properties and the surface itself kept in the collider's user data
+fn build_ground(terrain: &Terrain, stretches: &[SurfaceStretch], collider_set: &mut ColliderSet) {
+    for stretch in stretches {
+        let points = terrain
+            .section(stretch.from, stretch.to)
+            .into_iter()
+            .map(|(x, y)| point![x, y])
+            .collect();
+        let collider = ColliderBuilder::polyline(points, None)
+            .friction(stretch.surface.friction())
+            .restitution(stretch.surface.restitution())
+            .user_data(stretch.surface.to_user_data())
+            .build();
+        collider_set.insert(collider);
+    }
+}
+
+// Spreads coins along the track with a fuel can every `FUEL_SPACING` units
+fn place_pickups(terrain: &Terrain) -> Vec<Pickup> {
+    let coins_per_fuel = (FUEL_SPACING / COIN_SPACING) as usize;
+    (1..)
+        .map(|i| i as f64 * COIN_SPACING)
+        .take_while(|x| *x < terrain.end_x())
+        .enumerate()
+        .map(|(i, x)| Pickup {
+            x,
+            y: terrain.height_at(x) + PICKUP_HEIGHT,
+            kind: if (i + 1) % coins_per_fuel == 0 {
+                PickupKind::Fuel
+            } else {
+                PickupKind::Coin
+            },
+            collected: false,
+        })
+        .collect()
+}
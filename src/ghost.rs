@@ -0,0 +1,139 @@
+use crate::game::Game;
+use crate::input::TimedInput;
+
+/// Position and rotation of a body
+#[derive(Clone, Copy)]
+pub struct Pose {
+    pub x: f64,
+    pub y: f64,
+    pub angle: f64,
+}
+
+/// Poses of the car's body and wheels at one physics tick
+#[derive(Clone, Copy)]
+pub struct CarPose {
+    pub body: Pose,
+    pub front_wheel: Pose,
+    pub rear_wheel: Pose,
+}
+
+impl CarPose {
+    pub fn of(game: &Game) -> CarPose {
+        CarPose {
+            body: Pose {
+                x: game.get_car_body_x(),
+                y: game.get_car_body_y(),
+                angle: game.get_car_body_angle(),
+            },
+            front_wheel: Pose {
+                x: game.get_front_wheel_x(),
+                y: game.get_front_wheel_y(),
+                angle: game.get_front_wheel_angle(),
+            },
+            rear_wheel: Pose {
+                x: game.get_rear_wheel_x(),
+                y: game.get_rear_wheel_y(),
+                angle: game.get_rear_wheel_angle(),
+            },
+        }
+    }
+}
+
+/// The car's pose after every physics tick of a run, replayed as a ghost car
+#[derive(Clone, Default)]
+pub struct Trajectory {
+    // Physics steps the run had taken before the first pose, when recording
+    // didn't start with the run
+    start: u64,
+    poses: Vec<CarPose>,
+    // Farthest body x reached by each tick, never decreasing so it can be
+    // binary searched
+    farthest: Vec<f64>,
+}
+
+impl Trajectory {
+    /// Steps `game` through `inputs` the same way [`sim::run`](crate::sim::run)
+    /// does, recording the car's pose after every tick
+    pub fn simulate(game: &mut Game, inputs: &[TimedInput], duration: f64) -> Trajectory {
+        let mut trajectory = Trajectory::default();
+        let mut inputs = inputs.iter().peekable();
+        while game.get_stats().time < duration {
+            while let Some(input) = inputs.next_if(|input| input.tick <= game.get_tick()) {
+                game.apply_input(input.input);
+            }
+            game.step_physics();
+            trajectory.record(game);
+
+            if game.get_crash().is_some() || game.is_finished() {
+                break;
+            }
+        }
+        trajectory
+    }
+
+    pub fn record(&mut self, game: &Game) {
+        if self.poses.is_empty() {
+            self.start = game.get_tick().saturating_sub(1);
+        }
+        let pose = CarPose::of(game);
+        let farthest = self
+            .farthest
+            .last()
+            .map_or(pose.body.x, |x| x.max(pose.body.x));
+        self.poses.push(pose);
+        self.farthest.push(farthest);
+    }
+
+    /// Drops the poses after `ticks` physics steps, for when the run is
+    /// rewound to that point
+    pub fn truncate(&mut self, ticks: u64) {
+        let len = ticks.saturating_sub(self.start) as usize;
+        self.poses.truncate(len);
+        self.farthest.truncate(len);
+    }
+
+    /// Whether the poses go back to the start of the run. Only then do they
+    /// line up with another run's ticks to be raced as a ghost.
+    pub fn is_from_start(&self) -> bool {
+        self.start == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.poses.clear();
+        self.farthest.clear();
+    }
+
+    /// Pose after `tick` physics steps, the last pose once the run is over
+    pub fn pose_at(&self, tick: u64) -> Option<&CarPose> {
+        let index = (tick as usize).saturating_sub(1);
+        self.poses.get(index).or(self.poses.last())
+    }
+
+    /// Number of physics steps it took to first reach `x`
+    pub fn ticks_to_reach(&self, x: f64) -> Option<u64> {
+        let index = self.farthest.partition_point(|farthest| *farthest < x);
+        (index < self.farthest.len()).then_some(index as u64 + 1)
+    }
+}
+
+/// How the current run compares to the ghost, shown like split times
+pub struct GhostDelta {
+    /// Seconds more than the ghost took to get to the car's position,
+    /// `None` once past the farthest point the ghost reached
+    pub time: Option<f64>,
+    /// Distance ahead of the ghost at the same time, negative when behind
+    pub distance: f64,
+}
+
+impl GhostDelta {
+    pub fn between(game: &Game, ghost: &Trajectory) -> Option<GhostDelta> {
+        let ghost_pose = ghost.pose_at(game.get_tick())?;
+        let time = ghost
+            .ticks_to_reach(game.get_car_body_x())
+            .map(|ticks| (game.get_tick() as f64 - ticks as f64) * game.get_time_step());
+        Some(GhostDelta {
+            time,
+            distance: game.get_car_body_x() - ghost_pose.body.x,
+        })
+    }
+}
@@ -0,0 +1,43 @@
+use std::path::Path;
+
+use color_eyre::eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+
+/// Rear wheel torque added or removed by each input
+pub const THROTTLE_STEP: f64 = 5000.0;
+
+/// Player input to the car, shared by the terminal game, replays and bots
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Input {
+    /// Open the throttle one step further forward
+    Accelerate,
+    /// Close the throttle one step, reversing once it passes zero
+    Decelerate,
+}
+
+/// An input and the physics step it is applied before, counted from zero
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimedInput {
+    pub tick: u64,
+    pub input: Input,
+}
+
+/// Inputs for a run written by hand or by a bot. Script files are TOML with
+/// an `inputs` array of `{ tick = 0, input = "accelerate" }` tables.
+#[derive(Clone, Default, Deserialize)]
+pub struct Script {
+    pub inputs: Vec<TimedInput>,
+}
+
+impl Script {
+    pub fn load(path: &Path) -> Result<Script> {
+        let contents = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Unable to read script {}", path.display()))?;
+        let mut script: Script = toml::from_str(&contents)
+            .wrap_err_with(|| format!("Invalid script {}", path.display()))?;
+        // Inputs on the same tick keep their order
+        script.inputs.sort_by_key(|input| input.tick);
+        Ok(script)
+    }
+}
@@ -0,0 +1,354 @@
+use std::path::Path;
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use crate::background::{LayerSpec, DEFAULT_LAYERS};
+use crate::biome::{Biome, BIOME_LENGTH};
+use crate::game::PickupKind;
+use crate::surface::Surface;
+use crate::terrain::Terrain;
+
+// Where generated terrain starts, leaving room behind the spawn point
+const TERRAIN_START: f64 = -200.0;
+
+/// Gravity on earth in units per second squared, which levels have unless
+/// they say otherwise
+pub const EARTH_GRAVITY: f64 = 9.81;
+
+/// Track the game is played on. Level files are TOML with the same fields,
+/// anything they leave out is taken from the default level, which generates
+/// its terrain from a seed and places checkpoints and pickups along it.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Level {
+    /// Seed for the generated terrain and background
+    pub seed: u64,
+    /// Length of the generated terrain past its start
+    pub length: f64,
+    /// Hand-placed ground as `[x, y]` points in order of increasing x,
+    /// replacing the generated terrain unless empty
+    pub terrain: Vec<(f64, f64)>,
+    /// Surface of the ground before the first of `surfaces`
+    pub surface: Surface,
+    /// Stretches of ground with their own surface, each lasting until the
+    /// next one starts. Generated terrain takes its biomes' surfaces when
+    /// left empty.
+    pub surfaces: Vec<SurfaceSpan>,
+    /// X coordinate the car starts at, just above the ground
+    pub spawn: f64,
+    /// X coordinates of the checkpoints, spread evenly along the track when
+    /// left out
+    pub checkpoints: Option<Vec<f64>>,
+    /// Coins and fuel cans, spread evenly along the track when left out
+    pub pickups: Option<Vec<PickupPlacement>>,
+    /// Fixed boxes standing on the ground
+    pub obstacles: Vec<Obstacle>,
+    /// Downward acceleration in units per second squared before the first of
+    /// `gravity_zones`
+    pub gravity: f64,
+    /// Stretches of track with their own gravity, each lasting until the
+    /// next one starts. Generated terrain scales `gravity` by its biomes'
+    /// when left empty.
+    pub gravity_zones: Vec<GravityZone>,
+    /// X coordinate of the finish line, just before the end of the terrain
+    /// when left out
+    pub finish: Option<f64>,
+    /// Scenery layers behind the track from farthest to nearest, clouds,
+    /// distant mountains and foothills when left out
+    pub background: Option<Vec<LayerSpec>>,
+}
+
+/// Surface of the ground from `from` onwards
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct SurfaceSpan {
+    pub from: f64,
+    pub surface: Surface,
+}
+
+/// Gravity from `from` onwards, in units per second squared
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct GravityZone {
+    pub from: f64,
+    pub gravity: f64,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct PickupPlacement {
+    pub x: f64,
+    /// Height of the pickup, a little above the ground when left out
+    pub y: Option<f64>,
+    pub kind: PickupKind,
+}
+
+/// Box of `width` by `height` with its bottom centered on the ground at `x`
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Obstacle {
+    pub x: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Level {
+    /// The default level with its terrain generated from `seed`
+    pub fn from_seed(seed: u64) -> Level {
+        Level {
+            seed,
+            ..Level::default()
+        }
+    }
+
+    /// Flat ground with nothing on it, to start a new level from
+    pub fn blank() -> Level {
+        Level {
+            terrain: vec![(-100.0, 0.0), (1000.0, 0.0)],
+            checkpoints: Some(Vec::new()),
+            pickups: Some(Vec::new()),
+            ..Level::default()
+        }
+    }
+
+    /// The level file at `path` if there is one, or otherwise the default
+    /// level generated from `seed` or the default seed
+    pub fn load_or_generate(path: Option<&Path>, seed: Option<u64>) -> Result<Level> {
+        match (path, seed) {
+            (Some(path), _) => Level::load(path),
+            (None, Some(seed)) => Ok(Level::from_seed(seed)),
+            (None, None) => Ok(Level::default()),
+        }
+    }
+
+    /// Reads a level file, checking that its values are usable
+    pub fn load(path: &Path) -> Result<Level> {
+        let contents = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Unable to read level {}", path.display()))?;
+        let level: Level = toml::from_str(&contents)
+            .wrap_err_with(|| format!("Invalid level {}", path.display()))?;
+        level
+            .validate()
+            .wrap_err_with(|| format!("Invalid level {}", path.display()))?;
+        Ok(level)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents = toml::to_string(self).wrap_err("Unable to serialize level")?;
+        std::fs::write(path, contents)
+            .wrap_err_with(|| format!("Unable to write level {}", path.display()))
+    }
+
+    /// The hand-placed terrain, or otherwise the one generated from the seed
+    pub fn terrain(&self) -> Terrain {
+        if self.is_generated() {
+            Terrain::generate(self.seed, TERRAIN_START, self.length)
+        } else {
+            Terrain::from_points(self.terrain.clone())
+        }
+    }
+
+    /// Whether the terrain is generated from the seed rather than placed
+    pub fn is_generated(&self) -> bool {
+        self.terrain.is_empty()
+    }
+
+    /// Biome of the ground at `x`, if the terrain is generated
+    pub fn biome_at(&self, x: f64) -> Option<Biome> {
+        self.is_generated().then(|| Biome::at(x))
+    }
+
+    /// The level's surface stretches, or otherwise where the surface changes
+    /// between the generated terrain's biomes
+    pub fn surface_spans(&self) -> Vec<SurfaceSpan> {
+        if !self.surfaces.is_empty() || !self.is_generated() {
+            return self.surfaces.clone();
+        }
+        self.biome_starts()
+            .map(|from| SurfaceSpan {
+                from,
+                surface: Biome::at(from).surface(),
+            })
+            .collect()
+    }
+
+    /// The level's gravity zones, or otherwise where the gravity changes
+    /// between the generated terrain's biomes
+    pub fn gravity_zones(&self) -> Vec<GravityZone> {
+        if !self.gravity_zones.is_empty() || !self.is_generated() {
+            return self.gravity_zones.clone();
+        }
+        self.biome_starts()
+            .map(|from| GravityZone {
+                from,
+                gravity: self.gravity * Biome::at(from).gravity(),
+            })
+            .collect()
+    }
+
+    /// Surface of the ground at `x`
+    pub fn surface_at(&self, x: f64) -> Surface {
+        self.surface_spans()
+            .iter()
+            .rev()
+            .find(|span| span.from <= x)
+            .map_or(self.surface, |span| span.surface)
+    }
+
+    /// Downward acceleration at `x`
+    pub fn gravity_at(&self, x: f64) -> f64 {
+        gravity_at(self.gravity, &self.gravity_zones(), x)
+    }
+
+    // Where each biome after the first starts on the generated terrain
+    fn biome_starts(&self) -> impl Iterator<Item = f64> {
+        let end = TERRAIN_START + self.length;
+        (1..)
+            .map(|i| i as f64 * BIOME_LENGTH)
+            .take_while(move |x| *x < end)
+    }
+
+    /// Checks that the level can be played
+    pub fn validate(&self) -> Result<()> {
+        if !self.length.is_finite() || self.length <= 0.0 {
+            return Err(eyre!("length must be positive, got {}", self.length));
+        }
+        if !self.gravity.is_finite() || self.gravity <= 0.0 {
+            return Err(eyre!("gravity must be positive, got {}", self.gravity));
+        }
+
+        if !self.terrain.is_empty() {
+            if self.terrain.len() < 2 {
+                return Err(eyre!("terrain needs at least two points"));
+            }
+            if let Some((x, y)) = self
+                .terrain
+                .iter()
+                .find(|(x, y)| !x.is_finite() || !y.is_finite())
+            {
+                return Err(eyre!("terrain point [{}, {}] is not finite", x, y));
+            }
+            if let Some(pair) = self.terrain.windows(2).find(|pair| pair[1].0 <= pair[0].0) {
+                return Err(eyre!(
+                    "terrain points must go left to right, {} comes after {}",
+                    pair[1].0,
+                    pair[0].0
+                ));
+            }
+        }
+
+        let terrain = self.terrain();
+        let (start, end) = (terrain.start_x(), terrain.end_x());
+        let on_track = |what: &str, x: f64| {
+            if x.is_finite() && x >= start && x <= end {
+                Ok(())
+            } else {
+                Err(eyre!(
+                    "{} at {} is off the track, which runs from {} to {}",
+                    what,
+                    x,
+                    start,
+                    end
+                ))
+            }
+        };
+
+        on_track("spawn", self.spawn)?;
+        if let Some(finish) = self.finish {
+            on_track("finish", finish)?;
+            if finish <= self.spawn {
+                return Err(eyre!("finish at {} must be past the spawn", finish));
+            }
+        }
+        for checkpoint in self.checkpoints.iter().flatten() {
+            on_track("checkpoint", *checkpoint)?;
+        }
+        for pickup in self.pickups.iter().flatten() {
+            on_track("pickup", pickup.x)?;
+            if pickup.y.is_some_and(|y| !y.is_finite()) {
+                return Err(eyre!("pickup at {} has no finite height", pickup.x));
+            }
+        }
+        for obstacle in &self.obstacles {
+            on_track("obstacle", obstacle.x)?;
+            let size_valid = |size: f64| size.is_finite() && size > 0.0;
+            if !size_valid(obstacle.width) || !size_valid(obstacle.height) {
+                return Err(eyre!(
+                    "obstacle at {} must have a positive width and height",
+                    obstacle.x
+                ));
+            }
+        }
+        for span in &self.surfaces {
+            on_track("surface", span.from)?;
+        }
+        if let Some(pair) = self
+            .surfaces
+            .windows(2)
+            .find(|pair| pair[1].from <= pair[0].from)
+        {
+            return Err(eyre!(
+                "surfaces must go left to right, {} comes after {}",
+                pair[1].from,
+                pair[0].from
+            ));
+        }
+        for zone in &self.gravity_zones {
+            on_track("gravity zone", zone.from)?;
+            if !zone.gravity.is_finite() || zone.gravity <= 0.0 {
+                return Err(eyre!(
+                    "gravity zone at {} must have a positive gravity, got {}",
+                    zone.from,
+                    zone.gravity
+                ));
+            }
+        }
+        if let Some(pair) = self
+            .gravity_zones
+            .windows(2)
+            .find(|pair| pair[1].from <= pair[0].from)
+        {
+            return Err(eyre!(
+                "gravity zones must go left to right, {} comes after {}",
+                pair[1].from,
+                pair[0].from
+            ));
+        }
+        for layer in self.background_layers() {
+            layer.validate()?;
+        }
+
+        Ok(())
+    }
+
+    /// The level's background layers, or the default ones
+    pub fn background_layers(&self) -> &[LayerSpec] {
+        self.background.as_deref().unwrap_or(&DEFAULT_LAYERS)
+    }
+}
+
+impl Default for Level {
+    fn default() -> Level {
+        Level {
+            seed: 0x5EED,
+            length: 10000.0,
+            terrain: Vec::new(),
+            surface: Surface::default(),
+            surfaces: Vec::new(),
+            spawn: 0.0,
+            checkpoints: None,
+            pickups: None,
+            obstacles: Vec::new(),
+            gravity: EARTH_GRAVITY,
+            gravity_zones: Vec::new(),
+            finish: None,
+            background: None,
+        }
+    }
+}
+
+/// Gravity at `x` given the gravity before the first of `zones`
+pub(crate) fn gravity_at(gravity: f64, zones: &[GravityZone], x: f64) -> f64 {
+    zones
+        .iter()
+        .rev()
+        .find(|zone| zone.from <= x)
+        .map_or(gravity, |zone| zone.gravity)
+}
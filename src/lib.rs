@@ -0,0 +1,50 @@
+//! A 2D hill climb racing game simulated with rapier.
+//!
+//! The simulation lives in [`Game`], built from a [`Car`] and a [`Level`] and
+//! driven by [`Input`]s, and doesn't depend on any terminal crates. The
+//! terminal front end is behind the `tui` feature, which is on by default.
+//!
+//! Runs are reproducible given the same level, car, time step and inputs.
+//! To get the same results on every platform, build with the `deterministic`
+//! feature, which can't be combined with `simd`, then compare
+//! [`Game::state_hash`]es.
+
+pub mod background;
+pub mod biome;
+pub mod car;
+pub mod game;
+pub mod input;
+pub mod level;
+pub mod replay;
+pub mod rewind;
+pub mod sim;
+pub mod snapshot;
+pub mod surface;
+pub mod telemetry;
+pub mod terrain;
+
+#[cfg(feature = "tui")]
+pub mod app;
+#[cfg(feature = "tui")]
+pub mod diagnostics;
+#[cfg(feature = "tui")]
+pub mod editor;
+#[cfg(feature = "tui")]
+pub mod ghost;
+#[cfg(feature = "tui")]
+pub mod particles;
+#[cfg(feature = "tui")]
+pub mod settings;
+#[cfg(feature = "tui")]
+pub mod theme;
+#[cfg(feature = "tui")]
+pub mod tui;
+
+pub use car::Car;
+pub use game::{Game, Stats};
+pub use input::Input;
+pub use level::Level;
+pub use replay::Replay;
+pub use snapshot::Snapshot;
+// Game exposes rapier's body and collider sets
+pub use rapier2d_f64;
@@ -1,14 +1,121 @@
-use crate::app::App;
+use clap::Parser;
+use color_eyre::eyre::{eyre, Result};
+use ratatui::crossterm::{
+    event::{DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture},
+    execute,
+};
 
-mod app;
-mod game;
-mod tui;
+use ratatui_hillclimb::{
+    app::App, editor::Editor, input::Script, settings::Settings, sim, telemetry::TelemetryRecorder,
+    theme::Theme, Car, Game, Level, Replay, Snapshot,
+};
+
+use crate::cli::Cli;
+
+mod cli;
 
 #[tokio::main]
-async fn main() -> color_eyre::Result<()> {
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let level = Level::load_or_generate(cli.level.as_deref(), cli.seed)?;
+    let car = Car::load_or_default(cli.vehicle.as_deref())?;
+
+    let replay = cli.replay.as_deref().map(Replay::load).transpose()?;
+    let ghost = cli.ghost.as_deref().map(Replay::load).transpose()?;
+    if let (Some(path), Some(ghost)) = (&cli.ghost, &ghost) {
+        // Only runs that weren't rewound count as records
+        if ghost.rewinds > 0 {
+            return Err(eyre!(
+                "Replay {} was rewound, so it can't be raced as a ghost",
+                path.display()
+            ));
+        }
+    }
+    let telemetry = cli
+        .telemetry
+        .as_deref()
+        .map(|path| TelemetryRecorder::open(path, cli.telemetry_rate, cli.telemetry_max_bytes))
+        .transpose()?;
+
+    if cli.headless {
+        return run_headless(&cli, car, level, replay, telemetry);
+    }
+
+    let mut settings = match &cli.settings {
+        Some(path) => Settings::load(path)?,
+        None => Settings::default(),
+    };
+    settings.ascii |= cli.ascii;
+    settings.colorblind |= cli.colorblind;
+
+    let mut app = App::new(car, level)
+        .with_rates(cli.tick_rate, cli.frame_rate)
+        .with_settings(settings);
+    if let Some(marker) = cli.marker {
+        app = app.with_marker(marker.into());
+    }
+    if let Some(theme) = &cli.theme {
+        app = app.with_theme(Theme::find(theme)?);
+    }
+    if let Some(replay) = replay {
+        app = app.with_replay(replay);
+    }
+    if let Some(ghost) = &ghost {
+        app = app.with_ghost(ghost);
+    }
+    if let Some(path) = cli.record {
+        app = app.with_recording(path);
+    }
+    if let Some(recorder) = telemetry {
+        app = app.with_telemetry(recorder);
+    }
+    if let Some(path) = cli.edit {
+        let level = if path.exists() {
+            Level::load(&path)?
+        } else {
+            Level::blank()
+        };
+        app = app.with_editor(Editor::new(level, path));
+    }
+    if let Some(path) = cli.save {
+        // Resume the game saved on the last exit
+        if path.exists() {
+            app = app.with_snapshot(Snapshot::load(&path)?);
+        }
+        app = app.with_save_path(path);
+    }
+
     let mut terminal = ratatui::init();
     terminal.clear()?;
-    let result = App::default().run(&mut terminal).await;
+    execute!(std::io::stdout(), EnableFocusChange, EnableMouseCapture)?;
+    let result = app.run(&mut terminal).await;
+    execute!(std::io::stdout(), DisableFocusChange, DisableMouseCapture)?;
     ratatui::restore();
     result
 }
+
+// Simulates the run as fast as possible and prints the result, the same as
+// hillclimb-sim
+fn run_headless(
+    cli: &Cli,
+    car: Car,
+    level: Level,
+    replay: Option<Replay>,
+    mut telemetry: Option<TelemetryRecorder>,
+) -> Result<()> {
+    let (mut game, inputs) = match replay {
+        Some(replay) => (replay.game(), replay.inputs),
+        None => {
+            let script = match &cli.script {
+                Some(path) => Script::load(path)?,
+                None => Script::default(),
+            };
+            let game = Game::new(car, level).with_time_step(1.0 / cli.tick_rate);
+            (game, script.inputs)
+        }
+    };
+    let result = sim::run(&mut game, &inputs, cli.duration, telemetry.as_mut())?;
+    println!("{}", serde_json::to_string(&result)?);
+    Ok(())
+}
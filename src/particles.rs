@@ -0,0 +1,191 @@
+use std::f64::consts::TAU;
+
+use crate::game::Game;
+
+// Upper bound on live particles so long sessions can't pile them up
+const MAX_PARTICLES: usize = 600;
+const PARTICLE_GRAVITY: f64 = -30.0;
+const DUST_LIFETIME: f64 = 0.6;
+// Dust particles per step from each grounded wheel at full throttle
+const DUST_PER_STEP: f64 = 2.0;
+const EXHAUST_LIFETIME: f64 = 1.2;
+// Seconds between exhaust puffs while the throttle is open
+const EXHAUST_INTERVAL: f64 = 0.15;
+const SPARK_LIFETIME: f64 = 0.4;
+// Contact force per spark, harder impacts throw more sparks
+const FORCE_PER_SPARK: f64 = 5000.0;
+const MAX_SPARKS_PER_IMPACT: usize = 30;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ParticleKind {
+    Dust,
+    Exhaust,
+    Spark,
+}
+
+pub struct Particle {
+    pub kind: ParticleKind,
+    pub x: f64,
+    pub y: f64,
+    velocity: (f64, f64),
+    age: f64,
+    lifetime: f64,
+}
+
+impl Particle {
+    /// Fraction of the particle's lifetime that is left
+    pub fn remaining(&self) -> f64 {
+        1.0 - self.age / self.lifetime
+    }
+}
+
+/// Purely visual particles, simulated outside of rapier
+pub struct Particles {
+    particles: Vec<Particle>,
+    exhaust_timer: f64,
+    rng_state: u64,
+}
+
+impl Default for Particles {
+    fn default() -> Particles {
+        Particles {
+            particles: Vec::with_capacity(MAX_PARTICLES),
+            exhaust_timer: 0.0,
+            rng_state: 0x2545_F491_4F6C_DD1D,
+        }
+    }
+}
+
+impl Particles {
+    /// Advances existing particles by one physics step and emits new ones
+    /// from the car's state after that step.
+    pub fn update(&mut self, game: &Game) {
+        let dt = game.get_time_step();
+        for particle in self.particles.iter_mut() {
+            particle.age += dt;
+            if particle.kind != ParticleKind::Exhaust {
+                particle.velocity.1 += PARTICLE_GRAVITY * dt;
+            }
+            particle.x += particle.velocity.0 * dt;
+            particle.y += particle.velocity.1 * dt;
+        }
+        self.particles
+            .retain(|particle| particle.age < particle.lifetime);
+
+        self.emit_dust(game);
+        self.emit_exhaust(game, dt);
+        self.emit_sparks(game);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Particle> {
+        self.particles.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    fn emit_dust(&mut self, game: &Game) {
+        let throttle = game.get_throttle();
+        if throttle == 0.0 {
+            return;
+        }
+
+        let car = game.get_car();
+        let wheels = [
+            (
+                game.is_rear_wheel_grounded(),
+                game.get_rear_wheel_x(),
+                game.get_rear_wheel_y() - car.rear_wheel_radius,
+            ),
+            (
+                game.is_front_wheel_grounded(),
+                game.get_front_wheel_x(),
+                game.get_front_wheel_y() - car.front_wheel_radius,
+            ),
+        ];
+        let count = (throttle.abs() * DUST_PER_STEP).ceil() as usize;
+        for (grounded, x, y) in wheels {
+            if !grounded {
+                continue;
+            }
+
+            for _ in 0..count {
+                // Kicked up behind the wheel, away from the direction of travel
+                let velocity = (
+                    -throttle.signum() * (10.0 + 20.0 * self.random()),
+                    5.0 + 15.0 * self.random(),
+                );
+                self.spawn(ParticleKind::Dust, (x, y), velocity, DUST_LIFETIME);
+            }
+        }
+    }
+
+    fn emit_exhaust(&mut self, game: &Game, dt: f64) {
+        self.exhaust_timer -= dt;
+        if game.get_throttle() == 0.0 || self.exhaust_timer > 0.0 {
+            return;
+        }
+        self.exhaust_timer = EXHAUST_INTERVAL;
+
+        // The tailpipe sits at the lower back corner of the body
+        let car = game.get_car();
+        let angle = game.get_car_body_angle();
+        let (local_x, local_y) = (-car.body_half_width, -car.body_half_height / 2.0);
+        let position = (
+            game.get_car_body_x() + local_x * angle.cos() - local_y * angle.sin(),
+            game.get_car_body_y() + local_x * angle.sin() + local_y * angle.cos(),
+        );
+        let velocity = (
+            -8.0 * angle.cos() + 4.0 * (self.random() - 0.5),
+            4.0 + 4.0 * self.random(),
+        );
+        self.spawn(ParticleKind::Exhaust, position, velocity, EXHAUST_LIFETIME);
+    }
+
+    fn emit_sparks(&mut self, game: &Game) {
+        for impact in game.get_impacts() {
+            let count = ((impact.force / FORCE_PER_SPARK) as usize).min(MAX_SPARKS_PER_IMPACT);
+            for _ in 0..count {
+                let direction = self.random() * TAU;
+                let speed = 20.0 + 40.0 * self.random();
+                let velocity = (speed * direction.cos(), speed * direction.sin().abs());
+                self.spawn(
+                    ParticleKind::Spark,
+                    (impact.x, impact.y),
+                    velocity,
+                    SPARK_LIFETIME,
+                );
+            }
+        }
+    }
+
+    fn spawn(
+        &mut self,
+        kind: ParticleKind,
+        position: (f64, f64),
+        velocity: (f64, f64),
+        lifetime: f64,
+    ) {
+        if self.particles.len() >= MAX_PARTICLES {
+            return;
+        }
+
+        self.particles.push(Particle {
+            kind,
+            x: position.0,
+            y: position.1,
+            velocity,
+            age: 0.0,
+            lifetime,
+        });
+    }
+
+    // xorshift64, plenty for scattering particles
+    fn random(&mut self) -> f64 {
+        self.rng_state ^= self.rng_state << 13;
+        self.rng_state ^= self.rng_state >> 7;
+        self.rng_state ^= self.rng_state << 17;
+        (self.rng_state >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
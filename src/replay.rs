@@ -0,0 +1,73 @@
+use std::path::Path;
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use crate::car::Car;
+use crate::game::Game;
+use crate::input::TimedInput;
+use crate::level::Level;
+
+/// Everything needed to play a run back: the level, the car, the physics time
+/// step, every input with the tick it was applied on and how many times the
+/// run was rewound. Replay files are TOML with the same layout.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub level: Level,
+    pub car: Car,
+    pub time_step: f64,
+    pub inputs: Vec<TimedInput>,
+    /// Rewinding drops the inputs it undid, so the count is kept to stop
+    /// rewound runs from counting as records
+    #[serde(default)]
+    pub rewinds: u32,
+}
+
+impl Replay {
+    /// Recording of the run in `game` so far
+    pub fn of(game: &Game) -> Replay {
+        Replay {
+            level: game.get_level().clone(),
+            car: *game.get_car(),
+            time_step: game.get_time_step(),
+            inputs: game.get_inputs().to_vec(),
+            rewinds: game.get_stats().rewinds,
+        }
+    }
+
+    /// A fresh game to play the replay back in
+    pub fn game(&self) -> Game {
+        Game::new(self.car, self.level.clone())
+            .with_time_step(self.time_step)
+            .with_rewinds(self.rewinds)
+    }
+
+    /// Reads a replay file, checking that its level, car and time step are
+    /// usable
+    pub fn load(path: &Path) -> Result<Replay> {
+        let contents = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Unable to read replay {}", path.display()))?;
+        let mut replay: Replay = toml::from_str(&contents)
+            .wrap_err_with(|| format!("Invalid replay {}", path.display()))?;
+        replay
+            .validate()
+            .wrap_err_with(|| format!("Invalid replay {}", path.display()))?;
+        // Inputs on the same tick keep their order
+        replay.inputs.sort_by_key(|input| input.tick);
+        Ok(replay)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if !self.time_step.is_finite() || self.time_step <= 0.0 {
+            return Err(eyre!("time_step must be positive, got {}", self.time_step));
+        }
+        self.level.validate()?;
+        self.car.validate()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents = toml::to_string(self).wrap_err("Unable to serialize replay")?;
+        std::fs::write(path, contents)
+            .wrap_err_with(|| format!("Unable to write replay {}", path.display()))
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::VecDeque;
+
+use crate::game::Game;
+use crate::snapshot::Snapshot;
+
+// Physics steps between snapshots, and so the length of one rewind step
+const SNAPSHOT_INTERVAL: u64 = 15;
+
+/// Snapshots of the last few seconds of a run to rewind to
+pub struct RewindBuffer {
+    snapshots: VecDeque<Snapshot>,
+    // Simulated seconds of history to keep
+    seconds: f64,
+}
+
+impl RewindBuffer {
+    pub fn new(seconds: f64) -> RewindBuffer {
+        RewindBuffer {
+            snapshots: VecDeque::new(),
+            seconds,
+        }
+    }
+
+    /// Takes a snapshot of `game` when one is due and forgets the ones that
+    /// have fallen out of the buffer
+    pub fn record(&mut self, game: &Game) {
+        let tick = game.get_tick();
+        let recorded = self
+            .snapshots
+            .back()
+            .is_some_and(|snapshot| snapshot.get_tick() >= tick);
+        if !tick.is_multiple_of(SNAPSHOT_INTERVAL) || recorded {
+            return;
+        }
+
+        self.snapshots.push_back(game.snapshot());
+        let oldest_tick = tick as f64 - self.seconds / game.get_time_step();
+        while self
+            .snapshots
+            .front()
+            .is_some_and(|snapshot| (snapshot.get_tick() as f64) < oldest_tick)
+        {
+            self.snapshots.pop_front();
+        }
+    }
+
+    /// Removes and returns the latest snapshot from before the current tick
+    /// of `game`, `None` once the buffer has run out
+    pub fn step_back(&mut self, game: &Game) -> Option<Snapshot> {
+        while let Some(snapshot) = self.snapshots.pop_back() {
+            if snapshot.get_tick() < game.get_tick() {
+                return Some(snapshot);
+            }
+        }
+        None
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+}
@@ -0,0 +1,43 @@
+use std::path::Path;
+
+use color_eyre::eyre::{Result, WrapErr};
+use serde::Deserialize;
+
+/// Rendering preferences, loaded from a TOML file and overridable from the
+/// command line
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Draw only plain ASCII characters, for terminals without braille,
+    /// box drawing or block glyphs
+    pub ascii: bool,
+    /// Swap red/green color pairs for ones that stay distinct with color
+    /// vision deficiencies
+    pub colorblind: bool,
+    /// Draw the parallax scenery, can be turned off for slow terminals
+    pub show_background: bool,
+    /// Seconds of the run that can be rewound
+    pub rewind_seconds: f64,
+    /// Seconds of history plotted by the telemetry graphs
+    pub graph_seconds: f64,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            ascii: false,
+            colorblind: false,
+            show_background: true,
+            rewind_seconds: 10.0,
+            graph_seconds: 10.0,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load(path: &Path) -> Result<Settings> {
+        let contents = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Unable to read settings {}", path.display()))?;
+        toml::from_str(&contents).wrap_err_with(|| format!("Invalid settings {}", path.display()))
+    }
+}
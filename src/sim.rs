@@ -0,0 +1,83 @@
+use color_eyre::eyre::Result;
+use serde::Serialize;
+
+use crate::game::{Crash, Game, DETERMINISTIC};
+use crate::input::TimedInput;
+use crate::telemetry::TelemetryRecorder;
+
+/// Simulated seconds a headless run lasts unless told otherwise
+pub const DEFAULT_DURATION: f64 = 600.0;
+
+/// Final state of a simulated run
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct RunResult {
+    pub distance: f64,
+    pub max_distance: f64,
+    /// Simulated seconds the run lasted
+    pub time: f64,
+    pub coins: u32,
+    /// [`Stats::air_score`](crate::Stats::air_score) at the end of the run
+    pub air_score: f64,
+    pub fuel: f64,
+    /// Times the run was rewound, rewound runs don't count as records
+    pub rewinds: u32,
+    /// Whether the car crossed the finish line
+    pub finished: bool,
+    /// Why the run ended early, `None` if it finished or ran out of time
+    pub crash: Option<Crash>,
+    /// [`Game::state_hash`] at the end of the run
+    pub state_hash: u64,
+    /// Whether the physics were built with the `deterministic` feature, only
+    /// then do hashes match across platforms
+    pub deterministic: bool,
+}
+
+impl RunResult {
+    pub fn from_game(game: &Game) -> RunResult {
+        let stats = game.get_stats();
+        RunResult {
+            distance: stats.distance,
+            max_distance: stats.max_distance,
+            time: stats.time,
+            coins: stats.coins,
+            air_score: stats.air_score,
+            rewinds: stats.rewinds,
+            fuel: game.get_fuel(),
+            finished: game.is_finished(),
+            crash: game.get_crash(),
+            state_hash: game.state_hash(),
+            deterministic: DETERMINISTIC,
+        }
+    }
+}
+
+/// Steps `game` as fast as possible until the car crashes, finishes or
+/// `duration` simulated seconds have passed. Each input is applied right
+/// before the physics step of its tick, `inputs` must be sorted by tick.
+/// Every step is offered to `telemetry` when one is given.
+pub fn run(
+    game: &mut Game,
+    inputs: &[TimedInput],
+    duration: f64,
+    mut telemetry: Option<&mut TelemetryRecorder>,
+) -> Result<RunResult> {
+    let mut inputs = inputs.iter().peekable();
+    while game.get_stats().time < duration {
+        while let Some(input) = inputs.next_if(|input| input.tick <= game.get_tick()) {
+            game.apply_input(input.input);
+        }
+        game.step_physics();
+        if let Some(recorder) = telemetry.as_deref_mut() {
+            recorder.record(game)?;
+        }
+
+        if game.get_crash().is_some() || game.is_finished() {
+            break;
+        }
+    }
+
+    if let Some(recorder) = telemetry {
+        recorder.flush()?;
+    }
+    Ok(RunResult::from_game(game))
+}
@@ -0,0 +1,101 @@
+use std::path::Path;
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use nalgebra::SVector;
+use rapier2d_f64::dynamics::{
+    CCDSolver, ImpulseJointSet, IntegrationParameters, IslandManager, MultibodyJointSet,
+    RigidBodyHandle, RigidBodySet,
+};
+use rapier2d_f64::geometry::{ColliderHandle, ColliderSet, DefaultBroadPhase, NarrowPhase};
+use rapier2d_f64::prelude::nalgebra;
+use serde::{Deserialize, Serialize};
+
+use crate::car::Car;
+use crate::game::{Pickup, Stats};
+use crate::input::TimedInput;
+use crate::level::Level;
+
+/// Complete state of a [`Game`](crate::Game), from which it carries on
+/// exactly as if it had never been interrupted. Snapshot files are bincode.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub(crate) car: Car,
+    pub(crate) level: Level,
+    pub(crate) pickups: Vec<Pickup>,
+    pub(crate) fuel: f64,
+    pub(crate) tick: u64,
+    pub(crate) stats: Stats,
+    pub(crate) inputs: Vec<TimedInput>,
+    pub(crate) gravity: SVector<f64, 2>,
+    pub(crate) integration_parameters: IntegrationParameters,
+    pub(crate) rigid_body_set: RigidBodySet,
+    pub(crate) collider_set: ColliderSet,
+    pub(crate) impulse_joint_set: ImpulseJointSet,
+    pub(crate) multibody_joint_set: MultibodyJointSet,
+    pub(crate) island_manager: IslandManager,
+    pub(crate) broad_phase: DefaultBroadPhase,
+    pub(crate) narrow_phase: NarrowPhase,
+    pub(crate) ccd_solver: CCDSolver,
+    pub(crate) car_body_handle: RigidBodyHandle,
+    pub(crate) front_wheel_handle: RigidBodyHandle,
+    pub(crate) rear_wheel_handle: RigidBodyHandle,
+    pub(crate) car_body_collider_handle: ColliderHandle,
+    pub(crate) front_wheel_collider_handle: ColliderHandle,
+    pub(crate) rear_wheel_collider_handle: ColliderHandle,
+}
+
+impl Snapshot {
+    /// Physics steps the game had taken when the snapshot was taken
+    pub fn get_tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Reads a snapshot file, checking that its level, car, time step and
+    /// car bodies are usable
+    pub fn load(path: &Path) -> Result<Snapshot> {
+        let contents = std::fs::read(path)
+            .wrap_err_with(|| format!("Unable to read snapshot {}", path.display()))?;
+        let snapshot: Snapshot = bincode::deserialize(&contents)
+            .wrap_err_with(|| format!("Invalid snapshot {}", path.display()))?;
+        snapshot
+            .validate()
+            .wrap_err_with(|| format!("Invalid snapshot {}", path.display()))?;
+        Ok(snapshot)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let time_step = self.integration_parameters.dt;
+        if !time_step.is_finite() || time_step <= 0.0 {
+            return Err(eyre!("time_step must be positive, got {}", time_step));
+        }
+        self.level.validate()?;
+        self.car.validate()?;
+
+        let bodies = [
+            self.car_body_handle,
+            self.front_wheel_handle,
+            self.rear_wheel_handle,
+        ];
+        let colliders = [
+            self.car_body_collider_handle,
+            self.front_wheel_collider_handle,
+            self.rear_wheel_collider_handle,
+        ];
+        if bodies
+            .iter()
+            .any(|handle| !self.rigid_body_set.contains(*handle))
+            || colliders
+                .iter()
+                .any(|handle| !self.collider_set.contains(*handle))
+        {
+            return Err(eyre!("the car's bodies are missing"));
+        }
+        Ok(())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents = bincode::serialize(self).wrap_err("Unable to serialize snapshot")?;
+        std::fs::write(path, contents)
+            .wrap_err_with(|| format!("Unable to write snapshot {}", path.display()))
+    }
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+
+/// What a stretch of ground is made of, which sets how well the wheels grip,
+/// how much the car bounces off it and how much it holds the wheels back
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Surface {
+    Asphalt,
+    Dirt,
+    #[default]
+    Grass,
+    Ice,
+    Mud,
+    Sand,
+}
+
+impl Surface {
+    /// Every surface, in the order they're declared and the editor cycles
+    /// through them
+    pub const ALL: [Surface; 6] = [
+        Surface::Asphalt,
+        Surface::Dirt,
+        Surface::Grass,
+        Surface::Ice,
+        Surface::Mud,
+        Surface::Sand,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Surface::Asphalt => "asphalt",
+            Surface::Dirt => "dirt",
+            Surface::Grass => "grass",
+            Surface::Ice => "ice",
+            Surface::Mud => "mud",
+            Surface::Sand => "sand",
+        }
+    }
+
+    pub fn friction(self) -> f64 {
+        match self {
+            Surface::Asphalt => 0.9,
+            Surface::Dirt => 0.6,
+            // Rapier's default, which all ground used to have
+            Surface::Grass => 0.5,
+            Surface::Ice => 0.05,
+            Surface::Mud => 0.4,
+            Surface::Sand => 0.45,
+        }
+    }
+
+    /// Bounciness between 0 and 1
+    pub fn restitution(self) -> f64 {
+        match self {
+            Surface::Asphalt => 0.1,
+            Surface::Ice => 0.1,
+            Surface::Dirt | Surface::Grass | Surface::Mud | Surface::Sand => 0.0,
+        }
+    }
+
+    /// Fraction of the wheels' spin lost per second while rolling on it, on
+    /// top of what friction takes
+    pub fn rolling_resistance(self) -> f64 {
+        match self {
+            Surface::Asphalt | Surface::Grass | Surface::Ice => 0.0,
+            Surface::Dirt => 0.2,
+            Surface::Mud => 1.2,
+            Surface::Sand => 0.8,
+        }
+    }
+
+    /// The surface after this one in [`Surface::ALL`], wrapping around
+    pub fn next(self) -> Surface {
+        Surface::ALL[(self as usize + 1) % Surface::ALL.len()]
+    }
+
+    // Ground colliders carry their surface in their user data, offset by one
+    // so colliders that aren't ground keep the default of zero
+    pub(crate) fn to_user_data(self) -> u128 {
+        self as u128 + 1
+    }
+
+    pub(crate) fn from_user_data(data: u128) -> Option<Surface> {
+        let index = usize::try_from(data.checked_sub(1)?).ok()?;
+        Surface::ALL.get(index).copied()
+    }
+}
@@ -0,0 +1,292 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use serde::Serialize;
+
+use crate::game::Game;
+
+const CSV_HEADER: &str = "tick,time,x,y,angle,velocity_x,velocity_y,angular_velocity,\
+front_wheel_angular_velocity,rear_wheel_angular_velocity,torque,\
+front_wheel_contact,rear_wheel_contact,body_contact,fuel";
+// Limits on the rows per simulated second, in Hz
+const SAMPLE_RATE_RANGE: (f64, f64) = (1.0, 1000.0);
+
+/// State of the car after one physics step
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct TelemetryRow {
+    pub tick: u64,
+    pub time: f64,
+    pub x: f64,
+    pub y: f64,
+    pub angle: f64,
+    pub velocity_x: f64,
+    pub velocity_y: f64,
+    pub angular_velocity: f64,
+    pub front_wheel_angular_velocity: f64,
+    pub rear_wheel_angular_velocity: f64,
+    /// Rear wheel motor torque, positive driving forward
+    pub torque: f64,
+    pub front_wheel_contact: bool,
+    pub rear_wheel_contact: bool,
+    pub body_contact: bool,
+    pub fuel: f64,
+}
+
+impl TelemetryRow {
+    pub fn of(game: &Game) -> TelemetryRow {
+        let (velocity_x, velocity_y) = game.get_car_velocity();
+        TelemetryRow {
+            tick: game.get_tick(),
+            time: game.get_stats().time,
+            x: game.get_car_body_x(),
+            y: game.get_car_body_y(),
+            angle: game.get_car_body_angle(),
+            velocity_x,
+            velocity_y,
+            angular_velocity: game.get_car_angular_velocity(),
+            front_wheel_angular_velocity: game.get_front_wheel_angular_velocity(),
+            rear_wheel_angular_velocity: game.get_rear_wheel_angular_velocity(),
+            torque: game.get_drive_torque(),
+            front_wheel_contact: game.is_front_wheel_grounded(),
+            rear_wheel_contact: game.is_rear_wheel_grounded(),
+            body_contact: game.is_car_body_grounded(),
+            fuel: game.get_fuel(),
+        }
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.velocity_x.hypot(self.velocity_y)
+    }
+
+    fn to_csv(self) -> String {
+        // Contacts as 0 and 1 so spreadsheets can plot them
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.tick,
+            self.time,
+            self.x,
+            self.y,
+            self.angle,
+            self.velocity_x,
+            self.velocity_y,
+            self.angular_velocity,
+            self.front_wheel_angular_velocity,
+            self.rear_wheel_angular_velocity,
+            self.torque,
+            self.front_wheel_contact as u8,
+            self.rear_wheel_contact as u8,
+            self.body_contact as u8,
+            self.fuel
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TelemetryFormat {
+    Csv,
+    JsonLines,
+}
+
+impl TelemetryFormat {
+    /// Picks the format from a `.csv` or `.jsonl` extension
+    pub fn from_path(path: &Path) -> Result<TelemetryFormat> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("csv") => Ok(TelemetryFormat::Csv),
+            Some("jsonl") => Ok(TelemetryFormat::JsonLines),
+            _ => Err(eyre!(
+                "Telemetry file {} must end in .csv or .jsonl",
+                path.display()
+            )),
+        }
+    }
+}
+
+/// Writes a [`TelemetryRow`] every few physics steps to a CSV or JSON lines
+/// file. Once a file grows past the rotation size the rows continue in
+/// `name.1.csv`, `name.2.csv` and so on.
+pub struct TelemetryRecorder {
+    path: PathBuf,
+    format: TelemetryFormat,
+    // Rows per simulated second, every step when unset
+    sample_rate: Option<f64>,
+    rotate_bytes: Option<u64>,
+    writer: BufWriter<File>,
+    written_bytes: u64,
+    part: u32,
+}
+
+impl TelemetryRecorder {
+    /// Starts a recording at `path`, in the format matching its extension
+    pub fn create(path: &Path) -> Result<TelemetryRecorder> {
+        let format = TelemetryFormat::from_path(path)?;
+        let mut recorder = TelemetryRecorder {
+            path: path.to_path_buf(),
+            format,
+            sample_rate: None,
+            rotate_bytes: None,
+            writer: open(path)?,
+            written_bytes: 0,
+            part: 0,
+        };
+        recorder.write_header()?;
+        Ok(recorder)
+    }
+
+    /// Starts a recording at `path` with the binaries' telemetry options,
+    /// every step and no rotation when they're unset
+    pub fn open(
+        path: &Path,
+        sample_rate: Option<f64>,
+        rotate_bytes: Option<u64>,
+    ) -> Result<TelemetryRecorder> {
+        if let Some(rate) = sample_rate {
+            let (min, max) = SAMPLE_RATE_RANGE;
+            if !(min..=max).contains(&rate) {
+                return Err(eyre!(
+                    "Invalid telemetry rate {}, must be between {} and {} Hz",
+                    rate,
+                    min,
+                    max
+                ));
+            }
+        }
+
+        let mut recorder = TelemetryRecorder::create(path)?;
+        if let Some(rate) = sample_rate {
+            recorder = recorder.with_sample_rate(rate);
+        }
+        if let Some(bytes) = rotate_bytes {
+            recorder = recorder.with_rotation(bytes);
+        }
+        Ok(recorder)
+    }
+
+    pub fn with_sample_rate(mut self, sample_rate: f64) -> Self {
+        self.sample_rate = Some(sample_rate);
+        self
+    }
+
+    /// Moves on to a new file once the current one reaches `bytes`
+    pub fn with_rotation(mut self, bytes: u64) -> Self {
+        self.rotate_bytes = Some(bytes);
+        self
+    }
+
+    /// Writes a row for the step `game` just took, if one is due
+    pub fn record(&mut self, game: &Game) -> Result<()> {
+        let interval = match self.sample_rate {
+            Some(rate) => ((1.0 / (rate * game.get_time_step())).round() as u64).max(1),
+            None => 1,
+        };
+        if !game.get_tick().is_multiple_of(interval) {
+            return Ok(());
+        }
+
+        if self
+            .rotate_bytes
+            .is_some_and(|bytes| self.written_bytes >= bytes)
+        {
+            self.rotate()?;
+        }
+
+        let row = TelemetryRow::of(game);
+        let line = match self.format {
+            TelemetryFormat::Csv => row.to_csv(),
+            TelemetryFormat::JsonLines => serde_json::to_string(&row)?,
+        };
+        self.write_line(&line)
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush().wrap_err("Unable to write telemetry")
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        self.flush()?;
+        self.part += 1;
+        let stem = self.path.file_stem().unwrap_or_default().to_string_lossy();
+        let extension = self.path.extension().unwrap_or_default().to_string_lossy();
+        let path = self
+            .path
+            .with_file_name(format!("{}.{}.{}", stem, self.part, extension));
+        self.writer = open(&path)?;
+        self.written_bytes = 0;
+        self.write_header()
+    }
+
+    fn write_header(&mut self) -> Result<()> {
+        match self.format {
+            TelemetryFormat::Csv => self.write_line(CSV_HEADER),
+            TelemetryFormat::JsonLines => Ok(()),
+        }
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<()> {
+        writeln!(self.writer, "{}", line).wrap_err("Unable to write telemetry")?;
+        self.written_bytes += line.len() as u64 + 1;
+        Ok(())
+    }
+}
+
+/// Rows of the last few simulated seconds, for plotting while playing
+pub struct TelemetryHistory {
+    rows: VecDeque<TelemetryRow>,
+    seconds: f64,
+}
+
+impl TelemetryHistory {
+    pub fn new(seconds: f64) -> TelemetryHistory {
+        TelemetryHistory {
+            rows: VecDeque::new(),
+            seconds,
+        }
+    }
+
+    /// Adds a row for the step `game` just took and forgets the rows that
+    /// have fallen out of the window
+    pub fn record(&mut self, game: &Game) {
+        let row = TelemetryRow::of(game);
+        while self
+            .rows
+            .front()
+            .is_some_and(|oldest| oldest.time < row.time - self.seconds)
+        {
+            self.rows.pop_front();
+        }
+        self.rows.push_back(row);
+    }
+
+    /// Forgets the rows after `tick`, for when the game jumps back in time
+    pub fn truncate(&mut self, tick: u64) {
+        while self.rows.back().is_some_and(|row| row.tick > tick) {
+            self.rows.pop_back();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.rows.clear();
+    }
+
+    /// Oldest row first
+    pub fn rows(&self) -> impl Iterator<Item = &TelemetryRow> {
+        self.rows.iter()
+    }
+
+    pub fn latest(&self) -> Option<&TelemetryRow> {
+        self.rows.back()
+    }
+
+    /// Simulated seconds of history kept
+    pub fn get_seconds(&self) -> f64 {
+        self.seconds
+    }
+}
+
+fn open(path: &Path) -> Result<BufWriter<File>> {
+    let file = File::create(path)
+        .wrap_err_with(|| format!("Unable to create telemetry file {}", path.display()))?;
+    Ok(BufWriter::new(file))
+}
@@ -0,0 +1,138 @@
+use crate::biome::Biome;
+
+// Horizontal distance between terrain samples
+const SAMPLE_SPACING: f64 = 4.0;
+// Level ground after the spawn point before the hills fade in
+const FLAT_LENGTH: f64 = 100.0;
+const RAMP_LENGTH: f64 = 150.0;
+// Distance over which the hills double in height
+const DIFFICULTY_LENGTH: f64 = 3000.0;
+// Most the hills are scaled up by however far the terrain goes
+const MAX_DIFFICULTY: f64 = 3.0;
+
+/// Ground profile as a line through points in order of increasing x
+pub struct Terrain {
+    points: Vec<(f64, f64)>,
+}
+
+impl Terrain {
+    /// Generates `length` units of hills starting at `start_x`, with level
+    /// ground around x = 0 for the car to spawn on. The hills change with
+    /// each [`Biome`] and get steeper the farther they are from the start.
+    pub fn generate(seed: u64, start_x: f64, length: f64) -> Terrain {
+        let samples = (length / SAMPLE_SPACING).ceil() as usize + 1;
+        let points = (0..samples)
+            .map(|i| {
+                let x = start_x + i as f64 * SAMPLE_SPACING;
+                let ramp = ((x - FLAT_LENGTH) / RAMP_LENGTH).clamp(0.0, 1.0);
+                (x, ramp * difficulty(x) * biome_height(seed, x))
+            })
+            .collect();
+
+        Terrain { points }
+    }
+
+    /// Terrain through hand-placed `points`, which need at least two points
+    /// with strictly increasing x
+    pub fn from_points(points: Vec<(f64, f64)>) -> Terrain {
+        Terrain { points }
+    }
+
+    pub fn start_x(&self) -> f64 {
+        self.points[0].0
+    }
+
+    pub fn end_x(&self) -> f64 {
+        self.points[self.points.len() - 1].0
+    }
+
+    /// Ground height at `x`, linearly interpolated between points and
+    /// clamped to the ends of the terrain.
+    pub fn height_at(&self, x: f64) -> f64 {
+        let index = self.points.partition_point(|(point_x, _)| *point_x <= x);
+        if index == 0 {
+            return self.points[0].1;
+        }
+        if index == self.points.len() {
+            return self.points[index - 1].1;
+        }
+
+        let (x1, y1) = self.points[index - 1];
+        let (x2, y2) = self.points[index];
+        y1 + (y2 - y1) * (x - x1) / (x2 - x1)
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.points.iter().copied()
+    }
+
+    /// The points strictly between `from` and `to`, with the ground at
+    /// `from` and `to` added at either end
+    pub fn section(&self, from: f64, to: f64) -> Vec<(f64, f64)> {
+        let mut section = vec![(from, self.height_at(from))];
+        section.extend(self.points().filter(|(x, _)| *x > from && *x < to));
+        section.push((to, self.height_at(to)));
+        section
+    }
+
+    /// Downsamples the terrain between `from` and `to` into `samples` points,
+    /// keeping the highest point of each bucket so peaks are not lost.
+    pub fn profile(&self, from: f64, to: f64, samples: usize) -> Vec<(f64, f64)> {
+        let bucket_width = (to - from) / samples.max(1) as f64;
+        (0..samples)
+            .map(|bucket| {
+                let bucket_start = from + bucket as f64 * bucket_width;
+                let bucket_end = bucket_start + bucket_width;
+                let first = self.points.partition_point(|(x, _)| *x < bucket_start);
+                let peak = self.points[first..]
+                    .iter()
+                    .take_while(|(x, _)| *x < bucket_end)
+                    .map(|(_, height)| *height)
+                    .fold(self.height_at(bucket_start), f64::max);
+                (bucket_start + bucket_width / 2.0, peak)
+            })
+            .collect()
+    }
+}
+
+// How much the hills are scaled up at `x`
+fn difficulty(x: f64) -> f64 {
+    (1.0 + x.max(0.0) / DIFFICULTY_LENGTH).min(MAX_DIFFICULTY)
+}
+
+// Hills of the biome at `x`, blending into the next biome's near its end
+fn biome_height(seed: u64, x: f64) -> f64 {
+    let (biome, next, t) = Biome::transition(x);
+    let height = |biome: Biome| biome.amplitude() * terrain_noise(seed, x * biome.frequency());
+    let from = height(biome);
+    if t == 0.0 {
+        return from;
+    }
+    from + (height(next) - from) * t
+}
+
+// Sum of a few octaves of value noise, giving rolling hills with some bumps
+fn terrain_noise(seed: u64, x: f64) -> f64 {
+    value_noise(seed, x / 250.0) * 30.0
+        + value_noise(seed.wrapping_add(1), x / 90.0) * 12.0
+        + value_noise(seed.wrapping_add(2), x / 30.0) * 3.0
+}
+
+fn value_noise(seed: u64, x: f64) -> f64 {
+    let cell = x.floor();
+    let t = x - cell;
+    // Smoothstep keeps the slope continuous across lattice points
+    let t = t * t * (3.0 - 2.0 * t);
+    let a = lattice_value(seed, cell as i64);
+    let b = lattice_value(seed, cell as i64 + 1);
+    a + (b - a) * t
+}
+
+/// Pseudo random value in [-1, 1] for a lattice point, using splitmix64
+pub fn lattice_value(seed: u64, cell: i64) -> f64 {
+    let mut z = seed ^ (cell as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+}
@@ -0,0 +1,570 @@
+use std::{path::Path, str::FromStr};
+
+use color_eyre::eyre::{Result, WrapErr};
+use ratatui::{
+    style::Color,
+    symbols::{bar, Marker},
+    widgets::BorderType,
+};
+use serde::{de, Deserialize, Deserializer};
+
+use crate::surface::Surface;
+
+// Colors from the Okabe-Ito palette, distinguishable with the common color
+// vision deficiencies
+const SAFE_ORANGE: Color = Color::Rgb(230, 159, 0);
+const SAFE_VERMILLION: Color = Color::Rgb(213, 94, 0);
+const SAFE_BLUE: Color = Color::Rgb(0, 114, 178);
+const SAFE_SKY_BLUE: Color = Color::Rgb(86, 180, 233);
+const SAFE_YELLOW: Color = Color::Rgb(240, 228, 66);
+
+/// Colors and glyphs used to draw the game. Theme files are TOML with the same layout,
+/// anything they leave out is taken from the dark theme.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub name: String,
+    pub car: CarColors,
+    pub terrain: TerrainColors,
+    pub surfaces: SurfaceColors,
+    pub sky: SkyColors,
+    pub effects: EffectColors,
+    pub hud: HudColors,
+    pub borders: BorderColors,
+    pub debug: DebugColors,
+    pub glyphs: Glyphs,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct CarColors {
+    pub body: Color,
+    pub wheel: Color,
+    pub wheel_slip: Color,
+    /// Outline of the ghost car of the best run
+    pub ghost: Color,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct TerrainColors {
+    pub ground: Color,
+    pub checkpoint: Color,
+    pub checkpoint_passed: Color,
+    pub coin: Color,
+    pub fuel: Color,
+}
+
+/// Ground line and texture of each surface
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct SurfaceColors {
+    pub asphalt: Color,
+    pub dirt: Color,
+    pub grass: Color,
+    pub ice: Color,
+    pub mud: Color,
+    pub sand: Color,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct SkyColors {
+    pub background: Color,
+    pub clouds: Color,
+    pub far_mountains: Color,
+    pub near_mountains: Color,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct EffectColors {
+    pub dust: Color,
+    pub exhaust: Color,
+    pub exhaust_faded: Color,
+    pub spark: Color,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct HudColors {
+    pub text: Color,
+    pub speed: Color,
+    pub throttle: Color,
+    pub reverse: Color,
+    pub fuel: Color,
+    pub low_fuel: Color,
+    pub car_marker: Color,
+    pub personal_best: Color,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct BorderColors {
+    pub border: Color,
+    pub title: Color,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct DebugColors {
+    pub collider: Color,
+    pub joint: Color,
+    pub contact: Color,
+    pub velocity: Color,
+}
+
+/// Border style, canvas markers and HUD bars. Theme files name them in
+/// snake_case, such as `border = "double"` or `canvas_marker = "half_block"`.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct Glyphs {
+    #[serde(deserialize_with = "from_snake_case")]
+    pub border: BorderType,
+    /// Marker of the game canvas, unless one is given on the command line
+    #[serde(deserialize_with = "from_snake_case")]
+    pub canvas_marker: Marker,
+    #[serde(deserialize_with = "from_snake_case")]
+    pub minimap_marker: Marker,
+    #[serde(deserialize_with = "from_snake_case")]
+    pub graph_marker: Marker,
+    /// Whether gauges are filled with partial block characters rather than
+    /// whole cells
+    pub unicode_gauges: bool,
+    pub sparkline_bars: SparklineBars,
+}
+
+/// How finely the speed sparkline's bars are drawn
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SparklineBars {
+    /// Eight block heights per cell
+    Nine,
+    /// Half and full blocks
+    Three,
+}
+
+impl Theme {
+    pub fn load(path: &Path) -> Result<Theme> {
+        let contents = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Unable to read theme {}", path.display()))?;
+        toml::from_str(&contents).wrap_err_with(|| format!("Invalid theme {}", path.display()))
+    }
+
+    /// The preset called `name`, with dashes standing in for spaces, or
+    /// otherwise the theme file at that path
+    pub fn find(name: &str) -> Result<Theme> {
+        let preset_name = name.replace('-', " ");
+        match Theme::presets()
+            .into_iter()
+            .find(|theme| theme.name == preset_name)
+        {
+            Some(theme) => Ok(theme),
+            None => Theme::load(Path::new(name)),
+        }
+    }
+
+    /// The built-in themes, in the order they're cycled through
+    pub fn presets() -> Vec<Theme> {
+        vec![
+            Theme::dark(),
+            Theme::light(),
+            Theme::high_contrast(),
+            Theme::monochrome(),
+        ]
+    }
+
+    /// Copy of the theme where colors that are only told apart by red versus
+    /// green are replaced with ones from the Okabe-Ito palette. Monochrome
+    /// themes are left as they are.
+    pub fn colorblind_safe(&self) -> Theme {
+        let mut theme = self.clone();
+        let swap = |color: Color, safe: Color| {
+            if color == Color::Reset {
+                color
+            } else {
+                safe
+            }
+        };
+        theme.car.wheel_slip = swap(theme.car.wheel_slip, SAFE_ORANGE);
+        theme.terrain.ground = swap(theme.terrain.ground, SAFE_SKY_BLUE);
+        // Sky blue grass would be hard to tell apart from ice
+        theme.surfaces.grass = swap(theme.surfaces.grass, SAFE_YELLOW);
+        theme.terrain.fuel = swap(theme.terrain.fuel, SAFE_VERMILLION);
+        theme.hud.fuel = swap(theme.hud.fuel, SAFE_BLUE);
+        theme.hud.low_fuel = swap(theme.hud.low_fuel, SAFE_ORANGE);
+        theme
+    }
+
+    pub fn dark() -> Theme {
+        Theme {
+            name: "dark".to_string(),
+            car: CarColors {
+                body: Color::White,
+                wheel: Color::Gray,
+                wheel_slip: Color::Red,
+                ghost: Color::DarkGray,
+            },
+            terrain: TerrainColors {
+                ground: Color::Green,
+                checkpoint: Color::Blue,
+                checkpoint_passed: Color::DarkGray,
+                coin: Color::Yellow,
+                fuel: Color::Red,
+            },
+            surfaces: SurfaceColors {
+                asphalt: Color::Gray,
+                dirt: Color::Rgb(150, 110, 60),
+                grass: Color::Green,
+                ice: Color::LightCyan,
+                mud: Color::Rgb(110, 80, 50),
+                sand: Color::Rgb(220, 200, 130),
+            },
+            sky: SkyColors {
+                background: Color::Reset,
+                clouds: Color::White,
+                far_mountains: Color::DarkGray,
+                near_mountains: Color::Gray,
+            },
+            effects: EffectColors {
+                dust: Color::Rgb(150, 110, 60),
+                exhaust: Color::Gray,
+                exhaust_faded: Color::DarkGray,
+                spark: Color::LightYellow,
+            },
+            hud: HudColors {
+                text: Color::Reset,
+                speed: Color::Cyan,
+                throttle: Color::Yellow,
+                reverse: Color::Magenta,
+                fuel: Color::Green,
+                low_fuel: Color::Red,
+                car_marker: Color::Yellow,
+                personal_best: Color::Magenta,
+            },
+            borders: BorderColors {
+                border: Color::Reset,
+                title: Color::Reset,
+            },
+            debug: DebugColors {
+                collider: Color::LightCyan,
+                joint: Color::LightMagenta,
+                contact: Color::LightRed,
+                velocity: Color::LightYellow,
+            },
+            glyphs: Glyphs {
+                border: BorderType::Rounded,
+                canvas_marker: Marker::Dot,
+                minimap_marker: Marker::Braille,
+                graph_marker: Marker::Braille,
+                unicode_gauges: true,
+                sparkline_bars: SparklineBars::Nine,
+            },
+        }
+    }
+
+    pub fn light() -> Theme {
+        Theme {
+            name: "light".to_string(),
+            car: CarColors {
+                body: Color::Black,
+                wheel: Color::DarkGray,
+                wheel_slip: Color::Red,
+                ghost: Color::Rgb(180, 190, 205),
+            },
+            terrain: TerrainColors {
+                ground: Color::Rgb(40, 120, 40),
+                checkpoint: Color::Blue,
+                checkpoint_passed: Color::Gray,
+                coin: Color::Rgb(180, 140, 0),
+                fuel: Color::Red,
+            },
+            surfaces: SurfaceColors {
+                asphalt: Color::DarkGray,
+                dirt: Color::Rgb(130, 90, 40),
+                grass: Color::Rgb(40, 120, 40),
+                ice: Color::Rgb(70, 160, 200),
+                mud: Color::Rgb(90, 60, 30),
+                sand: Color::Rgb(190, 160, 80),
+            },
+            sky: SkyColors {
+                background: Color::Rgb(235, 242, 250),
+                clouds: Color::Gray,
+                far_mountains: Color::Rgb(170, 180, 200),
+                near_mountains: Color::Rgb(120, 130, 150),
+            },
+            effects: EffectColors {
+                dust: Color::Rgb(140, 100, 50),
+                exhaust: Color::DarkGray,
+                exhaust_faded: Color::Gray,
+                spark: Color::Rgb(230, 120, 0),
+            },
+            hud: HudColors {
+                text: Color::Black,
+                speed: Color::Blue,
+                throttle: Color::Rgb(200, 120, 0),
+                reverse: Color::Magenta,
+                fuel: Color::Rgb(40, 140, 40),
+                low_fuel: Color::Red,
+                car_marker: Color::Black,
+                personal_best: Color::Magenta,
+            },
+            borders: BorderColors {
+                border: Color::DarkGray,
+                title: Color::Black,
+            },
+            debug: DebugColors {
+                collider: Color::Blue,
+                joint: Color::Magenta,
+                contact: Color::Red,
+                velocity: Color::Rgb(200, 120, 0),
+            },
+            glyphs: Glyphs {
+                border: BorderType::Rounded,
+                canvas_marker: Marker::Dot,
+                minimap_marker: Marker::Braille,
+                graph_marker: Marker::Braille,
+                unicode_gauges: true,
+                sparkline_bars: SparklineBars::Nine,
+            },
+        }
+    }
+
+    pub fn high_contrast() -> Theme {
+        Theme {
+            name: "high contrast".to_string(),
+            car: CarColors {
+                body: Color::White,
+                wheel: Color::White,
+                wheel_slip: Color::LightRed,
+                ghost: Color::Gray,
+            },
+            terrain: TerrainColors {
+                ground: Color::LightGreen,
+                checkpoint: Color::LightCyan,
+                checkpoint_passed: Color::Gray,
+                coin: Color::LightYellow,
+                fuel: Color::LightRed,
+            },
+            surfaces: SurfaceColors {
+                asphalt: Color::Gray,
+                dirt: Color::Yellow,
+                grass: Color::LightGreen,
+                ice: Color::LightCyan,
+                mud: Color::LightMagenta,
+                sand: Color::LightYellow,
+            },
+            sky: SkyColors {
+                background: Color::Black,
+                clouds: Color::Gray,
+                far_mountains: Color::DarkGray,
+                near_mountains: Color::DarkGray,
+            },
+            effects: EffectColors {
+                dust: Color::LightYellow,
+                exhaust: Color::White,
+                exhaust_faded: Color::Gray,
+                spark: Color::LightYellow,
+            },
+            hud: HudColors {
+                text: Color::White,
+                speed: Color::LightCyan,
+                throttle: Color::LightYellow,
+                reverse: Color::LightMagenta,
+                fuel: Color::LightGreen,
+                low_fuel: Color::LightRed,
+                car_marker: Color::White,
+                personal_best: Color::LightMagenta,
+            },
+            borders: BorderColors {
+                border: Color::White,
+                title: Color::LightYellow,
+            },
+            debug: DebugColors {
+                collider: Color::LightCyan,
+                joint: Color::LightMagenta,
+                contact: Color::LightRed,
+                velocity: Color::LightYellow,
+            },
+            glyphs: Glyphs {
+                border: BorderType::Thick,
+                canvas_marker: Marker::Dot,
+                minimap_marker: Marker::Braille,
+                graph_marker: Marker::Braille,
+                unicode_gauges: false,
+                sparkline_bars: SparklineBars::Three,
+            },
+        }
+    }
+
+    /// Uses only the terminal's default colors
+    pub fn monochrome() -> Theme {
+        Theme {
+            name: "monochrome".to_string(),
+            car: CarColors {
+                body: Color::Reset,
+                wheel: Color::Reset,
+                wheel_slip: Color::Reset,
+                ghost: Color::Reset,
+            },
+            terrain: TerrainColors {
+                ground: Color::Reset,
+                checkpoint: Color::Reset,
+                checkpoint_passed: Color::Reset,
+                coin: Color::Reset,
+                fuel: Color::Reset,
+            },
+            surfaces: SurfaceColors {
+                asphalt: Color::Reset,
+                dirt: Color::Reset,
+                grass: Color::Reset,
+                ice: Color::Reset,
+                mud: Color::Reset,
+                sand: Color::Reset,
+            },
+            sky: SkyColors {
+                background: Color::Reset,
+                clouds: Color::Reset,
+                far_mountains: Color::Reset,
+                near_mountains: Color::Reset,
+            },
+            effects: EffectColors {
+                dust: Color::Reset,
+                exhaust: Color::Reset,
+                exhaust_faded: Color::Reset,
+                spark: Color::Reset,
+            },
+            hud: HudColors {
+                text: Color::Reset,
+                speed: Color::Reset,
+                throttle: Color::Reset,
+                reverse: Color::Reset,
+                fuel: Color::Reset,
+                low_fuel: Color::Reset,
+                car_marker: Color::Reset,
+                personal_best: Color::Reset,
+            },
+            borders: BorderColors {
+                border: Color::Reset,
+                title: Color::Reset,
+            },
+            debug: DebugColors {
+                collider: Color::Reset,
+                joint: Color::Reset,
+                contact: Color::Reset,
+                velocity: Color::Reset,
+            },
+            glyphs: Glyphs {
+                border: BorderType::Plain,
+                canvas_marker: Marker::Dot,
+                minimap_marker: Marker::Braille,
+                graph_marker: Marker::Braille,
+                unicode_gauges: false,
+                sparkline_bars: SparklineBars::Three,
+            },
+        }
+    }
+}
+
+impl SurfaceColors {
+    pub fn of(&self, surface: Surface) -> Color {
+        match surface {
+            Surface::Asphalt => self.asphalt,
+            Surface::Dirt => self.dirt,
+            Surface::Grass => self.grass,
+            Surface::Ice => self.ice,
+            Surface::Mud => self.mud,
+            Surface::Sand => self.sand,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::dark()
+    }
+}
+
+impl Default for CarColors {
+    fn default() -> CarColors {
+        Theme::dark().car
+    }
+}
+
+impl Default for TerrainColors {
+    fn default() -> TerrainColors {
+        Theme::dark().terrain
+    }
+}
+
+impl Default for SurfaceColors {
+    fn default() -> SurfaceColors {
+        Theme::dark().surfaces
+    }
+}
+
+impl Default for SkyColors {
+    fn default() -> SkyColors {
+        Theme::dark().sky
+    }
+}
+
+impl Default for EffectColors {
+    fn default() -> EffectColors {
+        Theme::dark().effects
+    }
+}
+
+impl Default for HudColors {
+    fn default() -> HudColors {
+        Theme::dark().hud
+    }
+}
+
+impl Default for BorderColors {
+    fn default() -> BorderColors {
+        Theme::dark().borders
+    }
+}
+
+impl Default for DebugColors {
+    fn default() -> DebugColors {
+        Theme::dark().debug
+    }
+}
+
+impl Default for Glyphs {
+    fn default() -> Glyphs {
+        Theme::dark().glyphs
+    }
+}
+
+impl SparklineBars {
+    pub fn set(self) -> bar::Set {
+        match self {
+            SparklineBars::Nine => bar::NINE_LEVELS,
+            SparklineBars::Three => bar::THREE_LEVELS,
+        }
+    }
+}
+
+// Reads one of ratatui's enums from its variant name in snake_case
+fn from_snake_case<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+{
+    let name = String::deserialize(deserializer)?;
+    let variant: String = name
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or(String::new(), |first| {
+                first.to_ascii_uppercase().to_string() + chars.as_str()
+            })
+        })
+        .collect();
+    variant
+        .parse()
+        .map_err(|_| de::Error::custom(format!("unknown name {}", name)))
+}
@@ -1,12 +1,16 @@
+use std::time::{Duration, Instant};
+
 use color_eyre::eyre::Result;
-use crossterm::event::{KeyEvent, KeyEventKind};
+use crossterm::event::{Event as CrosstermEvent, KeyEvent, KeyEventKind, MouseEvent};
 use futures::{FutureExt, StreamExt};
+use rapier2d_f64::{math::Point, na::point, parry::shape::TypedShape, prelude::nalgebra};
 use ratatui::{
-    layout::{Constraint, Layout},
+    layout::{Constraint, Layout, Rect},
     style::Color,
+    text::Line as TextLine,
     widgets::{
-        canvas::{Canvas, Circle, Line},
-        Block, BorderType, Paragraph, Widget,
+        canvas::{Canvas, Circle, Context, Line, Points, Rectangle},
+        Axis, Block, Chart, Dataset, Gauge, GraphType, Paragraph, Sparkline, Widget, Wrap,
     },
     Frame,
 };
@@ -15,7 +19,50 @@ use tokio::{
     task::JoinHandle,
 };
 
-use crate::app::App;
+use crate::{
+    app::App,
+    background::LayerKind,
+    car::Car,
+    editor::Editor,
+    game::{Game, PickupKind, FUEL_CAPACITY, MAX_TORQUE},
+    ghost::{CarPose, GhostDelta},
+    particles::ParticleKind,
+    surface::Surface,
+    telemetry::TelemetryRow,
+    terrain::Terrain,
+    theme::{CarColors, Theme},
+};
+
+// Half of the world area visible on the game canvas before the terminal size
+// is known
+const CAMERA_HALF_WIDTH: f64 = 180.0;
+const CAMERA_HALF_HEIGHT: f64 = 90.0;
+const CHECKPOINT_HEIGHT: f64 = 25.0;
+const CLOUD_WIDTH: f64 = 40.0;
+// Distance between the marks drawn along the ground to show its surface
+const TEXTURE_SPACING: f64 = 6.0;
+// Length of the arms of the editor's crosshair
+const EDITOR_CURSOR_SIZE: f64 = 6.0;
+// Mountain layers following the camera at least this much use the near color
+const NEAR_LAYER_PARALLAX: f64 = 0.2;
+const DIAGNOSTICS_WIDTH: u16 = 26;
+const GRAPHS_WIDTH: u16 = 36;
+const DEBUG_ANCHOR_RADIUS: f64 = 1.5;
+const DEBUG_NORMAL_LENGTH: f64 = 6.0;
+// Length of drawn velocity vectors per unit of speed
+const DEBUG_VELOCITY_SCALE: f64 = 0.5;
+// Space above and below the terrain in the minimap
+const MINIMAP_MARGIN: f64 = 5.0;
+const WHEEL_SPOKES: usize = 4;
+// Difference between wheel surface speed and ground speed above which the
+// wheel is drawn as slipping
+const WHEEL_SLIP_THRESHOLD: f64 = 2.0;
+// Speed at which the HUD speed gauge is full
+const HUD_MAX_SPEED: f64 = 60.0;
+// Fuel fraction below which the fuel gauge turns red
+const HUD_LOW_FUEL: f64 = 0.25;
+// Minimum HUD width for showing the gauges beside the run details
+const HUD_SPLIT_WIDTH: u16 = 70;
 
 #[derive(Clone, Copy)]
 pub enum Event {
@@ -24,6 +71,53 @@ pub enum Event {
     Tick,
     Render,
     Key(KeyEvent),
+    Mouse(MouseEvent),
+    Resize(u16, u16),
+    FocusLost,
+    FocusGained,
+}
+
+impl Event {
+    fn from_crossterm(event: CrosstermEvent) -> Option<Event> {
+        match event {
+            CrosstermEvent::Key(key) if key.kind == KeyEventKind::Press => Some(Event::Key(key)),
+            CrosstermEvent::Mouse(mouse) => Some(Event::Mouse(mouse)),
+            CrosstermEvent::Resize(width, height) => Some(Event::Resize(width, height)),
+            CrosstermEvent::FocusLost => Some(Event::FocusLost),
+            CrosstermEvent::FocusGained => Some(Event::FocusGained),
+            _ => None,
+        }
+    }
+}
+
+/// World area shown on the game canvas, centered on the car
+#[derive(Clone, Copy)]
+pub struct Camera {
+    pub half_width: f64,
+    pub half_height: f64,
+}
+
+impl Default for Camera {
+    fn default() -> Camera {
+        Camera {
+            half_width: CAMERA_HALF_WIDTH,
+            half_height: CAMERA_HALF_HEIGHT,
+        }
+    }
+}
+
+impl Camera {
+    /// Keeps the visible width and picks the height that keeps world units
+    /// square on a canvas of `area`, given cells about twice as tall as wide.
+    pub fn fit(area: Rect) -> Camera {
+        // Leave out the canvas border
+        let width = area.width.saturating_sub(2).max(1) as f64;
+        let height = area.height.saturating_sub(2).max(1) as f64;
+        Camera {
+            half_width: CAMERA_HALF_WIDTH,
+            half_height: CAMERA_HALF_WIDTH * 2.0 * height / width,
+        }
+    }
 }
 
 pub struct Tui {
@@ -34,6 +128,12 @@ pub struct Tui {
     frame_rate: f64,
 }
 
+impl Default for Tui {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Tui {
     // pub fn new() -> Self {
     //     Tui {
@@ -61,13 +161,8 @@ impl Tui {
                     maybe_event = crossterm_event => {
                         match maybe_event {
                             Some(Ok(evt)) => {
-                                match evt {
-                                    crossterm::event::Event::Key(key) => {
-                                        if key.kind == KeyEventKind::Press {
-                                            _event_tx.send(Event::Key(key)).unwrap();
-                                        }
-                                    },
-                                    _ => {}
+                                if let Some(event) = Event::from_crossterm(evt) {
+                                    _event_tx.send(event).unwrap();
                                 }
                             }
                             Some(Err(_)) => {
@@ -88,45 +183,13 @@ impl Tui {
     }
 
     pub fn new() -> Self {
-        let tick_rate = std::time::Duration::from_millis(250);
-
         let (event_tx, event_rx) = mpsc::unbounded_channel();
-        let _event_tx = event_tx.clone();
 
-        let task = tokio::spawn(async move {
-            let mut reader = crossterm::event::EventStream::new();
-            let mut interval = tokio::time::interval(tick_rate);
-            loop {
-                let delay = interval.tick();
-                let crossterm_event = reader.next().fuse();
-                tokio::select! {
-                    maybe_event = crossterm_event => {
-                        match maybe_event {
-                            Some(Ok(evt)) => {
-                                match evt {
-                                    crossterm::event::Event::Key(key) => {
-                                        if key.kind == crossterm::event::KeyEventKind::Press {
-                                            event_tx.send(Event::Key(key)).unwrap();
-                                        }
-                                    },
-                                    _ => {},
-                                }
-                            }
-                            Some(Err(_)) => {
-                                event_tx.send(Event::Error).unwrap();
-                            }
-                            None => {},
-                        }
-                    },
-                    _ = delay => {
-                        event_tx.send(Event::Tick).unwrap();
-                    },
-                }
-            }
-        });
+        // Placeholder until `start` spawns the event loop
+        let task = tokio::spawn(async {});
 
         Self {
-            event_tx: _event_tx,
+            event_tx,
             event_rx,
             task,
             tick_rate: 1.0,
@@ -141,6 +204,11 @@ impl Tui {
             .ok_or(color_eyre::eyre::eyre!("Unable to get event"))
     }
 
+    /// Number of events waiting to be received
+    pub fn queue_depth(&self) -> usize {
+        self.event_rx.len()
+    }
+
     pub fn tick_rate(mut self, tick_rate: f64) -> Self {
         self.tick_rate = tick_rate;
         self
@@ -152,6 +220,52 @@ impl Tui {
     }
 }
 
+/// Area of the game canvas, including its border, in a terminal of `area`
+pub fn canvas_area(area: Rect, app: &App) -> Rect {
+    layout(area, app)[0]
+}
+
+// Splits the terminal into the canvas, graphs, diagnostics, minimap and HUD
+// areas
+fn layout(area: Rect, app: &App) -> [Rect; 5] {
+    // Shrink the HUD on small terminals so the canvas stays usable
+    let hud_height = match area.height {
+        0..=24 => 3,
+        25..=40 => 6,
+        _ => 9,
+    };
+    // Hide the minimap when there isn't room for it
+    let minimap_height = if area.height > 24 { 6 } else { 0 };
+    let [top_area, minimap_area, hud_area] = Layout::vertical([
+        Constraint::Fill(1),
+        Constraint::Length(minimap_height),
+        Constraint::Length(hud_height),
+    ])
+    .margin(1)
+    .areas(area);
+
+    let graphs_width = if app.show_graphs { GRAPHS_WIDTH } else { 0 };
+    let diagnostics_width = if app.show_diagnostics {
+        DIAGNOSTICS_WIDTH
+    } else {
+        0
+    };
+    let [canvas_area, graphs_area, diagnostics_area] = Layout::horizontal([
+        Constraint::Fill(1),
+        Constraint::Length(graphs_width),
+        Constraint::Length(diagnostics_width),
+    ])
+    .areas(top_area);
+
+    [
+        canvas_area,
+        graphs_area,
+        diagnostics_area,
+        minimap_area,
+        hud_area,
+    ]
+}
+
 pub fn ui(frame: &mut Frame, app: &App) {
     // let page_block = Block::default()
     //     .borders(Borders::ALL)
@@ -160,123 +274,1018 @@ pub fn ui(frame: &mut Frame, app: &App) {
 
     // frame.render_widget(page_block, frame.area());
 
-    let chunks = Layout::vertical([Constraint::Fill(1), Constraint::Max(15)])
-        .margin(1)
-        .split(frame.area());
+    let [canvas_area, graphs_area, diagnostics_area, minimap_area, hud_area] =
+        layout(frame.area(), app);
 
-    frame.render_widget(game_canvas(app), chunks[0]);
-    frame.render_widget(draw_info(app), chunks[1]);
+    frame.render_widget(game_canvas(app), canvas_area);
+    if app.show_graphs {
+        draw_graphs(frame, app, graphs_area);
+    }
+    if app.show_diagnostics {
+        frame.render_widget(diagnostics_panel(app), diagnostics_area);
+    }
+    frame.render_widget(minimap(app, minimap_area.width), minimap_area);
+    draw_hud(frame, app, hud_area);
+
+    if app.settings.ascii {
+        for cell in frame.buffer_mut().content.iter_mut() {
+            if !cell.symbol().is_ascii() {
+                let symbol = ascii_symbol(cell.symbol());
+                cell.set_symbol(symbol);
+            }
+        }
+    }
+}
+
+// Closest plain ASCII stand-in for the glyphs drawn by the widgets
+fn ascii_symbol(symbol: &str) -> &'static str {
+    match symbol.chars().next().unwrap_or(' ') {
+        '\u{2800}' => " ",
+        '\u{2801}'..='\u{28FF}' | '·' => ".",
+        '•' => "*",
+        '─' | '━' | '═' => "-",
+        '│' | '┃' | '║' => "|",
+        '╭' | '╮' | '╰' | '╯' | '┌' | '┐' | '└' | '┘' | '├' | '┤' | '┬' | '┴' | '┼' => {
+            "+"
+        }
+        '▁' | '▂' | '▃' => "_",
+        '▄' | '▅' => "=",
+        '▆' | '▇' | '█' | '▀' | '▌' | '▐' | '▉' | '▊' | '▋' | '▍' | '▎' | '▏' | '▓' | '▒' | '░' => {
+            "#"
+        }
+        '°' => "o",
+        '²' => "2",
+        _ => "?",
+    }
 }
 
 fn game_canvas(app: &App) -> impl Widget + '_ {
+    // The camera follows the car, or the cursor while editing
+    let editor = app.editor.as_ref().filter(|_| app.editing);
+    let (camera_x, camera_y) = match editor {
+        Some(editor) => editor.cursor,
+        None => (app.game.get_car_body_x(), app.game.get_car_body_y()),
+    };
+
+    let Camera {
+        half_width,
+        half_height,
+    } = app.camera;
+    let theme = app.theme();
+    let mut title = match (editor, &app.editor) {
+        (Some(editor), _) if editor.modified => "Level Editor (modified)".to_string(),
+        (Some(_), _) => "Level Editor".to_string(),
+        (None, Some(_)) => "Game Canvas (test drive)".to_string(),
+        (None, None) => "Game Canvas".to_string(),
+    };
+    if app.is_replaying() {
+        title.push_str(" (replay)");
+    }
+    if app.paused {
+        title.push_str(" (paused)");
+    }
+    if let Some(status) = run_status(&app.game) {
+        title.push_str(&format!(" ({}, r to restart)", status));
+    }
+
     Canvas::default()
-        .block(
-            Block::bordered()
-                .border_type(BorderType::Rounded)
-                .title("Game Canvas"),
-        )
-        .marker(app.marker)
-        .paint(|ctx| {
-            // TODO: Refactor drawing the car out to a function that
-            // takes the game state for location and sizes
-            // Draw the car body
+        .block(bordered_block(title, theme))
+        .background_color(theme.sky.background)
+        .marker(app.marker.unwrap_or(theme.glyphs.canvas_marker))
+        .paint(move |ctx| {
+            if app.settings.show_background {
+                draw_background(ctx, app, (camera_x, camera_y));
+                ctx.layer();
+            }
+
+            draw_track(ctx, app, camera_x);
+            ctx.layer();
+
             let car = app.game.get_car();
-            let car_body_angle = app.game.get_car_body_angle();
-            // let car_body_vertical_angle = car_body_angle * (3.1415 / 2.0);
-            let car_center = (app.game.get_car_body_x(), app.game.get_car_body_y());
-            let top_right = (
-                car_center.0 + ((car.body_half_width) * car_body_angle.cos())
-                    - ((car.body_half_height) * car_body_angle.sin()),
-                car_center.1
-                    + ((car.body_half_width) * car_body_angle.sin())
-                    + ((car.body_half_height) * car_body_angle.cos()),
-            );
-            let top_left = (
-                car_center.0
-                    - ((car.body_half_width) * car_body_angle.cos())
-                    - ((car.body_half_height) * car_body_angle.sin()),
-                car_center.1 - ((car.body_half_width) * car_body_angle.sin())
-                    + ((car.body_half_height) * car_body_angle.cos()),
-            );
-            let bottom_right = (
-                car_center.0
-                    + ((car.body_half_width) * car_body_angle.cos())
-                    + ((car.body_half_height) * car_body_angle.sin()),
-                car_center.1 + ((car.body_half_width) * car_body_angle.sin())
-                    - ((car.body_half_height) * car_body_angle.cos()),
-            );
-            let bottom_left = (
-                car_center.0 - ((car.body_half_width) * car_body_angle.cos())
-                    + ((car.body_half_height) * car_body_angle.sin()),
-                car_center.1
-                    - ((car.body_half_width) * car_body_angle.sin())
-                    - ((car.body_half_height) * car_body_angle.cos()),
+            // Drawn first so the car covers it where they overlap
+            if let Some(pose) = app
+                .ghost
+                .as_ref()
+                .and_then(|ghost| ghost.pose_at(app.game.get_tick()))
+            {
+                let colors = CarColors {
+                    body: theme.car.ghost,
+                    wheel: theme.car.ghost,
+                    wheel_slip: theme.car.ghost,
+                    ghost: theme.car.ghost,
+                };
+                draw_car(ctx, car, pose, (0.0, 0.0), &colors);
+                ctx.layer();
+            }
+
+            let slip = (
+                app.game.get_front_wheel_slip(),
+                app.game.get_rear_wheel_slip(),
             );
+            draw_car(ctx, car, &CarPose::of(&app.game), slip, &theme.car);
 
-            // Draw car box
-            ctx.draw(&Line {
-                x1: bottom_left.0,
-                y1: bottom_left.1,
-                x2: bottom_right.0,
-                y2: bottom_right.1,
-                color: Color::White,
-            });
+            ctx.layer();
+            draw_particles(ctx, app);
+
+            if app.show_debug {
+                ctx.layer();
+                draw_physics_debug(ctx, app, camera_x);
+            }
+
+            if let Some(editor) = editor {
+                ctx.layer();
+                draw_editor(ctx, editor, theme, app.camera);
+            }
+        })
+        .x_bounds([camera_x - half_width, camera_x + half_width])
+        .y_bounds([camera_y - half_height, camera_y + half_height])
+}
+
+fn draw_background(ctx: &mut Context, app: &App, camera: (f64, f64)) {
+    let sky = &app.theme().sky;
+    for layer in app.game.get_background() {
+        let offset = layer.offset(camera);
+        let from = camera.0 - app.camera.half_width - offset.0;
+        let to = camera.0 + app.camera.half_width - offset.0;
+
+        match layer.kind {
+            LayerKind::Mountains => {
+                for index in layer.indices(from, to) {
+                    let (x1, y1) = layer.point(index);
+                    let (x2, y2) = layer.point(index + 1);
+                    if x2 < from || x1 > to {
+                        continue;
+                    }
+
+                    ctx.draw(&Line {
+                        x1: x1 + offset.0,
+                        y1: y1 + offset.1,
+                        x2: x2 + offset.0,
+                        y2: y2 + offset.1,
+                        color: if layer.parallax < NEAR_LAYER_PARALLAX {
+                            sky.far_mountains
+                        } else {
+                            sky.near_mountains
+                        },
+                    });
+                }
+            }
+            LayerKind::Clouds => {
+                for index in layer.indices(from - CLOUD_WIDTH, to + CLOUD_WIDTH) {
+                    let (x, y) = layer.point(index);
+                    draw_cloud(ctx, x + offset.0, y + offset.1, sky.clouds);
+                }
+            }
+        }
+    }
+}
+
+// A cloud is a stack of lines, widest in the middle
+fn draw_cloud(ctx: &mut Context, x: f64, y: f64, color: Color) {
+    for (height, width) in [(-3.0, 0.7), (0.0, 1.0), (3.0, 0.5)] {
+        ctx.draw(&Line {
+            x1: x - width * CLOUD_WIDTH / 2.0,
+            y1: y + height,
+            x2: x + width * CLOUD_WIDTH / 2.0,
+            y2: y + height,
+            color,
+        });
+    }
+}
+
+fn draw_track(ctx: &mut Context, app: &App, camera_x: f64) {
+    let game = &app.game;
+    let colors = &app.theme().terrain;
+    let left = camera_x - app.camera.half_width;
+    let right = camera_x + app.camera.half_width;
+
+    for stretch in game.get_surfaces() {
+        let (from, to) = (stretch.from.max(left), stretch.to.min(right));
+        if from >= to {
+            continue;
+        }
+
+        let color = app.theme().surfaces.of(stretch.surface);
+        for segment in game.get_terrain().section(from, to).windows(2) {
             ctx.draw(&Line {
-                x1: top_left.0,
-                y1: top_left.1,
-                x2: top_right.0,
-                y2: top_right.1,
-                color: Color::White,
+                x1: segment[0].0,
+                y1: segment[0].1,
+                x2: segment[1].0,
+                y2: segment[1].1,
+                color,
             });
-            ctx.draw(&Line {
-                x1: bottom_left.0,
-                y1: bottom_left.1,
-                x2: top_left.0,
-                y2: top_left.1,
-                color: Color::White,
+        }
+        draw_surface_texture(ctx, game.get_terrain(), stretch.surface, (from, to), color);
+    }
+
+    for checkpoint in game.get_checkpoints() {
+        if *checkpoint < left || *checkpoint > right {
+            continue;
+        }
+
+        let ground_y = game.get_terrain().height_at(*checkpoint);
+        ctx.draw(&Line {
+            x1: *checkpoint,
+            y1: ground_y,
+            x2: *checkpoint,
+            y2: ground_y + CHECKPOINT_HEIGHT,
+            color: colors.checkpoint,
+        });
+        ctx.draw(&Rectangle {
+            x: *checkpoint,
+            y: ground_y + CHECKPOINT_HEIGHT - 6.0,
+            width: 10.0,
+            height: 6.0,
+            color: colors.checkpoint,
+        });
+    }
+
+    for obstacle in game.get_obstacles() {
+        if obstacle.x + obstacle.width < left || obstacle.x - obstacle.width > right {
+            continue;
+        }
+
+        ctx.draw(&Rectangle {
+            x: obstacle.x - obstacle.width / 2.0,
+            y: game.get_terrain().height_at(obstacle.x),
+            width: obstacle.width,
+            height: obstacle.height,
+            color: colors.ground,
+        });
+    }
+
+    // The finish line is a flag twice the height of a checkpoint's
+    let finish = game.get_finish_x();
+    if finish >= left && finish <= right {
+        let ground_y = game.get_terrain().height_at(finish);
+        ctx.draw(&Line {
+            x1: finish,
+            y1: ground_y,
+            x2: finish,
+            y2: ground_y + CHECKPOINT_HEIGHT * 2.0,
+            color: colors.checkpoint,
+        });
+        for (row, column) in [(0.0, 0.0), (0.0, 2.0), (1.0, 1.0), (1.0, 3.0)] {
+            ctx.draw(&Rectangle {
+                x: finish + column * 5.0,
+                y: ground_y + CHECKPOINT_HEIGHT * 2.0 - (row + 1.0) * 5.0,
+                width: 5.0,
+                height: 5.0,
+                color: colors.checkpoint,
             });
-            ctx.draw(&Line {
-                x1: bottom_right.0,
-                y1: bottom_right.1,
-                x2: top_right.0,
-                y2: top_right.1,
-                color: Color::White,
+        }
+    }
+
+    for pickup in game.get_pickups() {
+        if pickup.collected || pickup.x < left || pickup.x > right {
+            continue;
+        }
+
+        match pickup.kind {
+            PickupKind::Coin => ctx.draw(&Circle {
+                x: pickup.x,
+                y: pickup.y,
+                radius: 2.0,
+                color: colors.coin,
+            }),
+            PickupKind::Fuel => ctx.draw(&Rectangle {
+                x: pickup.x - 2.5,
+                y: pickup.y - 3.0,
+                width: 5.0,
+                height: 6.0,
+                color: colors.fuel,
+            }),
+        }
+    }
+}
+
+// Marks along the ground telling the surfaces apart without relying on color.
+// Grass, the default surface, is left plain.
+fn draw_surface_texture(
+    ctx: &mut Context,
+    terrain: &Terrain,
+    surface: Surface,
+    (from, to): (f64, f64),
+    color: Color,
+) {
+    let first = (from / TEXTURE_SPACING).ceil() as i64;
+    let last = (to / TEXTURE_SPACING).floor() as i64;
+    // Alternates between two depths below the ground from mark to mark
+    let depth = |index: i64| 2.0 + index.rem_euclid(2) as f64 * 1.5;
+    for index in first..=last {
+        let x = index as f64 * TEXTURE_SPACING;
+        let y = terrain.height_at(x);
+        match surface {
+            Surface::Grass => {}
+            // Dashed line under the road
+            Surface::Asphalt if index.rem_euclid(2) == 0 => ctx.draw(&Line {
+                x1: x,
+                y1: y - 2.0,
+                x2: x + TEXTURE_SPACING / 2.0,
+                y2: terrain.height_at(x + TEXTURE_SPACING / 2.0) - 2.0,
+                color,
+            }),
+            Surface::Asphalt => {}
+            // Clods scattered at two depths
+            Surface::Dirt => ctx.draw(&Points {
+                coords: &[(x, y - depth(index))],
+                color,
+            }),
+            // Glints slanting up from the ground
+            Surface::Ice => ctx.draw(&Line {
+                x1: x,
+                y1: y + 0.5,
+                x2: x + 1.5,
+                y2: y + 2.5,
+                color,
+            }),
+            // Zigzag of sludge under the ground
+            Surface::Mud => ctx.draw(&Line {
+                x1: x,
+                y1: y - depth(index),
+                x2: x + TEXTURE_SPACING,
+                y2: terrain.height_at(x + TEXTURE_SPACING) - depth(index + 1),
+                color,
+            }),
+            // Rows of fine grains
+            Surface::Sand => ctx.draw(&Points {
+                coords: &[(x, y - 1.5), (x + TEXTURE_SPACING / 2.0, y - 3.0)],
+                color,
+            }),
+        }
+    }
+}
+
+fn minimap(app: &App, width: u16) -> impl Widget + '_ {
+    let game = &app.game;
+    let car = (game.get_car_body_x(), game.get_car_body_y());
+    let left = car.0 - app.minimap_window / 2.0;
+    let right = car.0 + app.minimap_window / 2.0;
+
+    // Two samples per cell, enough for the finest marker, braille
+    let profile = game.get_terrain().profile(left, right, width as usize * 2);
+    let (low, high) = profile.iter().fold((car.1, car.1), |(low, high), (_, y)| {
+        (low.min(*y), high.max(*y))
+    });
+
+    let theme = app.theme();
+
+    Canvas::default()
+        .block(bordered_block("Minimap", theme))
+        .background_color(theme.sky.background)
+        .marker(theme.glyphs.minimap_marker)
+        .paint(move |ctx| {
+            for segment in profile.windows(2) {
+                ctx.draw(&Line {
+                    x1: segment[0].0,
+                    y1: segment[0].1,
+                    x2: segment[1].0,
+                    y2: segment[1].1,
+                    color: theme.terrain.ground,
+                });
+            }
+
+            let reached = game.get_spawn_x() + game.get_stats().max_distance;
+            for checkpoint in game.get_checkpoints() {
+                ctx.draw(&Line {
+                    x1: *checkpoint,
+                    y1: low,
+                    x2: *checkpoint,
+                    y2: high,
+                    color: if *checkpoint <= reached {
+                        theme.terrain.checkpoint_passed
+                    } else {
+                        theme.terrain.checkpoint
+                    },
+                });
+            }
+
+            if app.personal_best > 0.0 {
+                let best = game.get_spawn_x() + app.personal_best;
+                ctx.draw(&Line {
+                    x1: best,
+                    y1: low,
+                    x2: best,
+                    y2: high,
+                    color: theme.hud.personal_best,
+                });
+            }
+
+            let fuel: Vec<(f64, f64)> = game
+                .get_pickups()
+                .iter()
+                .filter(|pickup| !pickup.collected && pickup.kind == PickupKind::Fuel)
+                .map(|pickup| (pickup.x, pickup.y))
+                .collect();
+            ctx.draw(&Points {
+                coords: &fuel,
+                color: theme.terrain.fuel,
             });
 
-            // Draw front wheel
-            ctx.draw(&Circle {
-                x: app.game.get_front_wheel_x(),
-                y: app.game.get_front_wheel_y(),
-                radius: car.rear_wheel_radius,
-                color: Color::Black,
+            ctx.layer();
+            ctx.draw(&Points {
+                coords: &[car],
+                color: theme.hud.car_marker,
             });
+        })
+        .x_bounds([left, right])
+        .y_bounds([low - MINIMAP_MARGIN, high + MINIMAP_MARGIN])
+}
+
+fn draw_physics_debug(ctx: &mut Context, app: &App, camera_x: f64) {
+    let game = &app.game;
+    let colors = &app.theme().debug;
+    let left = camera_x - app.camera.half_width;
+    let right = camera_x + app.camera.half_width;
+
+    for (_, collider) in game.get_collider_set().iter() {
+        let position = collider.position();
+        match collider.shape().as_typed_shape() {
+            TypedShape::Ball(ball) => ctx.draw(&Circle {
+                x: position.translation.x,
+                y: position.translation.y,
+                radius: ball.radius,
+                color: colors.collider,
+            }),
+            TypedShape::Cuboid(cuboid) => {
+                let (hx, hy) = (cuboid.half_extents.x, cuboid.half_extents.y);
+                let corners = [
+                    point![-hx, -hy],
+                    point![hx, -hy],
+                    point![hx, hy],
+                    point![-hx, hy],
+                ]
+                .map(|corner| position * corner);
+                for i in 0..corners.len() {
+                    let next = corners[(i + 1) % corners.len()];
+                    draw_segment(ctx, corners[i], next, colors.collider);
+                }
+            }
+            TypedShape::Polyline(polyline) => {
+                for segment in polyline.segments() {
+                    let (a, b) = (position * segment.a, position * segment.b);
+                    if a.x.max(b.x) >= left && a.x.min(b.x) <= right {
+                        draw_segment(ctx, a, b, colors.collider);
+                    }
+                }
+            }
+            // Anything else is drawn as its bounding box
+            _ => {
+                let aabb = collider.compute_aabb();
+                ctx.draw(&Rectangle {
+                    x: aabb.mins.x,
+                    y: aabb.mins.y,
+                    width: aabb.maxs.x - aabb.mins.x,
+                    height: aabb.maxs.y - aabb.mins.y,
+                    color: colors.collider,
+                });
+            }
+        }
+    }
 
-            // Draw rear wheel
+    let bodies = game.get_rigid_body_set();
+    for (_, joint) in game.get_impulse_joint_set().iter() {
+        let anchor1 = bodies[joint.body1].position() * joint.data.local_anchor1();
+        let anchor2 = bodies[joint.body2].position() * joint.data.local_anchor2();
+        draw_segment(ctx, anchor1, anchor2, colors.joint);
+        for anchor in [anchor1, anchor2] {
             ctx.draw(&Circle {
-                x: app.game.get_rear_wheel_x(),
-                y: app.game.get_rear_wheel_y(),
-                radius: car.front_wheel_radius,
-                color: Color::Black,
+                x: anchor.x,
+                y: anchor.y,
+                radius: DEBUG_ANCHOR_RADIUS,
+                color: colors.joint,
             });
-        })
-        .x_bounds([-180.0, 180.0])
-        .y_bounds([-90.0, 90.0])
+        }
+    }
+
+    for pair in game.get_narrow_phase().contact_pairs() {
+        for manifold in &pair.manifolds {
+            let normal = manifold.data.normal * DEBUG_NORMAL_LENGTH;
+            for contact in &manifold.data.solver_contacts {
+                draw_segment(ctx, contact.point, contact.point + normal, colors.contact);
+            }
+        }
+    }
+
+    for (_, body) in bodies.iter().filter(|(_, body)| body.is_dynamic()) {
+        let start = Point::from(*body.translation());
+        draw_segment(
+            ctx,
+            start,
+            start + body.linvel() * DEBUG_VELOCITY_SCALE,
+            colors.velocity,
+        );
+    }
+}
+
+fn draw_segment(ctx: &mut Context, a: Point<f64>, b: Point<f64>, color: Color) {
+    ctx.draw(&Line {
+        x1: a.x,
+        y1: a.y,
+        x2: b.x,
+        y2: b.y,
+        color,
+    });
 }
 
-fn draw_info(app: &App) -> impl Widget + '_ {
-    Paragraph::new(format!(
-        "torque: {} x: {} y: {}",
-        app.game.get_rear_wheel_torque(),
-        app.game.get_car_body_x(),
-        app.game.get_car_body_y()
-    ))
-    .block(
-        Block::bordered()
-            .border_type(BorderType::Rounded)
-            .title("Game Info"),
+// Marks the terrain's control points and draws the cursor as a crosshair
+fn draw_editor(ctx: &mut Context, editor: &Editor, theme: &Theme, camera: Camera) {
+    let (cursor_x, cursor_y) = editor.cursor;
+    let visible: Vec<(f64, f64)> = editor
+        .level
+        .terrain
+        .iter()
+        .copied()
+        .filter(|(x, _)| (x - cursor_x).abs() <= camera.half_width)
+        .collect();
+    ctx.draw(&Points {
+        coords: &visible,
+        color: theme.hud.car_marker,
+    });
+    if let Some(index) = editor.dragging {
+        let (x, y) = editor.level.terrain[index];
+        ctx.draw(&Circle {
+            x,
+            y,
+            radius: EDITOR_CURSOR_SIZE / 2.0,
+            color: theme.hud.personal_best,
+        });
+    }
+
+    ctx.draw(&Line {
+        x1: cursor_x - EDITOR_CURSOR_SIZE,
+        y1: cursor_y,
+        x2: cursor_x + EDITOR_CURSOR_SIZE,
+        y2: cursor_y,
+        color: theme.hud.personal_best,
+    });
+    ctx.draw(&Line {
+        x1: cursor_x,
+        y1: cursor_y - EDITOR_CURSOR_SIZE,
+        x2: cursor_x,
+        y2: cursor_y + EDITOR_CURSOR_SIZE,
+        color: theme.hud.personal_best,
+    });
+}
+
+fn draw_particles(ctx: &mut Context, app: &App) {
+    let colors = &app.theme().effects;
+    let mut dust = Vec::new();
+    let mut fresh_exhaust = Vec::new();
+    let mut old_exhaust = Vec::new();
+    let mut sparks = Vec::new();
+    for particle in app.particles.iter() {
+        let point = (particle.x, particle.y);
+        match particle.kind {
+            ParticleKind::Dust => dust.push(point),
+            ParticleKind::Exhaust if particle.remaining() > 0.5 => fresh_exhaust.push(point),
+            ParticleKind::Exhaust => old_exhaust.push(point),
+            ParticleKind::Spark => sparks.push(point),
+        }
+    }
+
+    for (coords, color) in [
+        (&dust, colors.dust),
+        (&fresh_exhaust, colors.exhaust),
+        (&old_exhaust, colors.exhaust_faded),
+        (&sparks, colors.spark),
+    ] {
+        ctx.draw(&Points { coords, color });
+    }
+}
+
+/// Draws the car's body and wheels at `pose`, `slip` being the front and rear
+/// wheel slip
+fn draw_car(ctx: &mut Context, car: &Car, pose: &CarPose, slip: (f64, f64), colors: &CarColors) {
+    let angle = pose.body.angle;
+    let center = (pose.body.x, pose.body.y);
+    let top_right = (
+        center.0 + ((car.body_half_width) * angle.cos()) - ((car.body_half_height) * angle.sin()),
+        center.1 + ((car.body_half_width) * angle.sin()) + ((car.body_half_height) * angle.cos()),
+    );
+    let top_left = (
+        center.0 - ((car.body_half_width) * angle.cos()) - ((car.body_half_height) * angle.sin()),
+        center.1 - ((car.body_half_width) * angle.sin()) + ((car.body_half_height) * angle.cos()),
+    );
+    let bottom_right = (
+        center.0 + ((car.body_half_width) * angle.cos()) + ((car.body_half_height) * angle.sin()),
+        center.1 + ((car.body_half_width) * angle.sin()) - ((car.body_half_height) * angle.cos()),
+    );
+    let bottom_left = (
+        center.0 - ((car.body_half_width) * angle.cos()) + ((car.body_half_height) * angle.sin()),
+        center.1 - ((car.body_half_width) * angle.sin()) - ((car.body_half_height) * angle.cos()),
+    );
+
+    // Draw car box
+    ctx.draw(&Line {
+        x1: bottom_left.0,
+        y1: bottom_left.1,
+        x2: bottom_right.0,
+        y2: bottom_right.1,
+        color: colors.body,
+    });
+    ctx.draw(&Line {
+        x1: top_left.0,
+        y1: top_left.1,
+        x2: top_right.0,
+        y2: top_right.1,
+        color: colors.body,
+    });
+    ctx.draw(&Line {
+        x1: bottom_left.0,
+        y1: bottom_left.1,
+        x2: top_left.0,
+        y2: top_left.1,
+        color: colors.body,
+    });
+    ctx.draw(&Line {
+        x1: bottom_right.0,
+        y1: bottom_right.1,
+        x2: top_right.0,
+        y2: top_right.1,
+        color: colors.body,
+    });
+
+    // Draw front wheel
+    let front_wheel = &pose.front_wheel;
+    draw_wheel(
+        ctx,
+        front_wheel.x,
+        front_wheel.y,
+        car.front_wheel_radius,
+        front_wheel.angle,
+        slip.0,
+        colors,
+    );
+
+    // Draw rear wheel
+    let rear_wheel = &pose.rear_wheel;
+    draw_wheel(
+        ctx,
+        rear_wheel.x,
+        rear_wheel.y,
+        car.rear_wheel_radius,
+        rear_wheel.angle,
+        slip.1,
+        colors,
+    );
+}
+
+fn draw_wheel(
+    ctx: &mut Context,
+    x: f64,
+    y: f64,
+    radius: f64,
+    angle: f64,
+    slip: f64,
+    colors: &CarColors,
+) {
+    let color = if slip.abs() > WHEEL_SLIP_THRESHOLD {
+        colors.wheel_slip
+    } else {
+        colors.wheel
+    };
+
+    ctx.draw(&Circle {
+        x,
+        y,
+        radius,
+        color,
+    });
+
+    for spoke in 0..WHEEL_SPOKES {
+        let spoke_angle = angle + (spoke as f64) * std::f64::consts::TAU / (WHEEL_SPOKES as f64);
+        ctx.draw(&Line {
+            x1: x,
+            y1: y,
+            x2: x + radius * spoke_angle.cos(),
+            y2: y + radius * spoke_angle.sin(),
+            color,
+        });
+    }
+}
+
+fn diagnostics_panel(app: &App) -> impl Widget + '_ {
+    let diagnostics = &app.diagnostics;
+    let milliseconds = |duration: Duration| duration.as_secs_f64() * 1000.0;
+    let now = Instant::now();
+    let lines = vec![
+        TextLine::from(format!("render  {:6.1} fps", diagnostics.frames.rate(now))),
+        TextLine::from(format!(
+            "physics {:6.1} steps/s",
+            diagnostics.steps.rate(now)
+        )),
+        TextLine::from(format!(
+            "step    avg {:.2} ms",
+            milliseconds(diagnostics.step_time.average())
+        )),
+        TextLine::from(format!(
+            "        p99 {:.2} ms",
+            milliseconds(diagnostics.step_time.percentile(0.99))
+        )),
+        TextLine::from(format!(
+            "draw    avg {:.2} ms",
+            milliseconds(diagnostics.draw_time.average())
+        )),
+        TextLine::from(format!(
+            "        p99 {:.2} ms",
+            milliseconds(diagnostics.draw_time.percentile(0.99))
+        )),
+        TextLine::from(format!("queue   {} events", diagnostics.queue_depth)),
+        TextLine::from(format!(
+            "bodies  {}  colliders {}",
+            app.game.get_rigid_body_set().len(),
+            app.game.get_collider_set().len()
+        )),
+    ];
+
+    let theme = app.theme();
+    Paragraph::new(lines)
+        .style(theme.hud.text)
+        .block(bordered_block("Diagnostics", theme))
+}
+
+// Plots the recent speed, altitude, body angle and wheel torque above each
+// other, with time running along the bottom
+fn draw_graphs(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme();
+    let history = &app.telemetry_history;
+    let block = bordered_block(
+        format!("Telemetry (last {:.0} s)", history.get_seconds()),
+        theme,
     )
+    .style(theme.hud.text);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
 
-    // let todo_info = Paragraph::bordered()
-    //     .border_type(BorderType::Rounded)
-    //     .title("Game Info");
+    let Some(latest) = history.latest() else {
+        return;
+    };
+    let x_bounds = [latest.time - history.get_seconds(), latest.time];
+    let series = |value: fn(&TelemetryRow) -> f64| -> Vec<(f64, f64)> {
+        history.rows().map(|row| (row.time, value(row))).collect()
+    };
+    let speed = series(TelemetryRow::speed);
+    let altitude = series(|row| row.y);
+    let angle = series(|row| row.angle.to_degrees());
+    let torque = series(|row| row.torque / MAX_TORQUE * 100.0);
+
+    let areas: [Rect; 4] = Layout::vertical([Constraint::Fill(1); 4]).areas(inner);
+    let graphs = [
+        (
+            format!("speed {:.1} m/s", latest.speed()),
+            &speed,
+            value_bounds(&speed),
+            theme.hud.speed,
+        ),
+        (
+            format!("altitude {:.1} m", latest.y),
+            &altitude,
+            value_bounds(&altitude),
+            theme.terrain.ground,
+        ),
+        (
+            format!("angle {:+.0}°", latest.angle.to_degrees()),
+            &angle,
+            value_bounds(&angle),
+            theme.car.body,
+        ),
+        (
+            format!("torque {:+.0}%", latest.torque / MAX_TORQUE * 100.0),
+            &torque,
+            [-100.0, 100.0],
+            theme.hud.throttle,
+        ),
+    ];
+    for ((title, points, y_bounds, color), area) in graphs.into_iter().zip(areas) {
+        let dataset = Dataset::default()
+            .marker(theme.glyphs.graph_marker)
+            .graph_type(GraphType::Line)
+            .style(color)
+            .data(points);
+        let chart = Chart::new(vec![dataset])
+            .block(Block::new().title(title))
+            .x_axis(Axis::default().bounds(x_bounds))
+            .y_axis(
+                Axis::default()
+                    .bounds(y_bounds)
+                    .labels([format!("{:.0}", y_bounds[0]), format!("{:.0}", y_bounds[1])]),
+            );
+        frame.render_widget(chart, area);
+    }
+}
+
+// Lowest and highest of the values plotted, spread apart when they're equal
+fn value_bounds(points: &[(f64, f64)]) -> [f64; 2] {
+    let (low, high) = points
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), (_, y)| {
+            (low.min(*y), high.max(*y))
+        });
+    if high - low < 1.0 {
+        [low - 0.5, low + 0.5]
+    } else {
+        [low, high]
+    }
+}
+
+fn bordered_block<'a>(title: impl Into<TextLine<'a>>, theme: &Theme) -> Block<'a> {
+    Block::bordered()
+        .border_type(theme.glyphs.border)
+        .border_style(theme.borders.border)
+        .title(title)
+        .title_style(theme.borders.title)
+}
+
+// Lists the editor's keys in place of the HUD, with the cursor position
+// and the outcome of the last save
+fn draw_editor_help(frame: &mut Frame, editor: &Editor, theme: &Theme, area: Rect) {
+    let (x, y) = editor.cursor;
+    let mut status = format!(
+        "cursor {:.0}, {:.0} on {}   gravity {:.2} m/s²   {} points   {}",
+        x,
+        y,
+        editor.level.surface_at(x).name(),
+        editor.level.gravity_at(x),
+        editor.level.terrain.len(),
+        editor.path.display()
+    );
+    if let Some(message) = &editor.message {
+        status.push_str(&format!("   {}", message));
+    }
+    let lines = vec![
+        TextLine::from(status),
+        TextLine::from("arrows move (shift faster)  a add point  m move point  x delete nearest"),
+        TextLine::from("c coin  f fuel  k checkpoint  s surface  w save  tab test drive  q quit"),
+    ];
+    frame.render_widget(
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: true })
+            .style(theme.hud.text)
+            .block(bordered_block("Level Editor", theme)),
+        area,
+    );
+}
+
+// How the run ended, if it has
+fn run_status(game: &Game) -> Option<String> {
+    if game.is_finished() {
+        return Some(format!("finished in {:.1} s", game.get_stats().time));
+    }
+    game.get_crash()
+        .map(|crash| format!("crashed, {}", crash.name()))
+}
+
+fn draw_hud(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme();
+    if let Some(editor) = app.editor.as_ref().filter(|_| app.editing) {
+        draw_editor_help(frame, editor, theme, area);
+        return;
+    }
+
+    let block = bordered_block("Game Info", theme).style(theme.hud.text);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let game = &app.game;
+    let stats = game.get_stats();
+    let speed = game.get_car_speed();
+    let throttle = game.get_throttle();
+    let fuel = game.get_fuel() / FUEL_CAPACITY;
+
+    // Too short for gauges, fall back to a single summary line
+    if inner.height < 3 {
+        let summary = format!(
+            "{:.1} m/s  fuel {:.0}%  {:.1} m  coins {}",
+            speed,
+            fuel * 100.0,
+            stats.distance,
+            stats.coins
+        );
+        frame.render_widget(Paragraph::new(summary), inner);
+        return;
+    }
+
+    let [gauges_area, details_area] = if inner.width >= HUD_SPLIT_WIDTH {
+        Layout::horizontal([Constraint::Percentage(40), Constraint::Fill(1)])
+            .spacing(2)
+            .areas(inner)
+    } else {
+        Layout::vertical([Constraint::Length(3), Constraint::Fill(1)]).areas(inner)
+    };
+
+    let [speed_area, throttle_area, fuel_area] =
+        Layout::vertical([Constraint::Length(1); 3]).areas(gauges_area);
+    frame.render_widget(
+        Gauge::default()
+            .use_unicode(theme.glyphs.unicode_gauges)
+            .gauge_style(theme.hud.speed)
+            .ratio((speed / HUD_MAX_SPEED).min(1.0))
+            .label(format!("speed {:.1} m/s", speed)),
+        speed_area,
+    );
+    frame.render_widget(
+        Gauge::default()
+            .use_unicode(theme.glyphs.unicode_gauges)
+            .gauge_style(if throttle < 0.0 {
+                theme.hud.reverse
+            } else {
+                theme.hud.throttle
+            })
+            .ratio(throttle.abs().min(1.0))
+            .label(format!("throttle {:+.0}%", throttle * 100.0)),
+        throttle_area,
+    );
+    frame.render_widget(
+        Gauge::default()
+            .use_unicode(theme.glyphs.unicode_gauges)
+            .gauge_style(if fuel < HUD_LOW_FUEL {
+                theme.hud.low_fuel
+            } else {
+                theme.hud.fuel
+            })
+            .ratio(fuel.clamp(0.0, 1.0))
+            .label(format!("fuel {:.0}%", fuel * 100.0)),
+        fuel_area,
+    );
+
+    let minutes = (stats.time / 60.0).floor();
+    let mut summary = format!(
+        "distance {:.1} m   best {:.1} m   coins {}",
+        stats.distance, stats.max_distance, stats.coins
+    );
+    if stats.rewinds > 0 {
+        summary.push_str(&format!("   rewinds {}", stats.rewinds));
+    }
+    if let Some(biome) = game.get_level().biome_at(game.get_car_body_x()) {
+        summary.push_str(&format!("   {}", biome.name()));
+    }
+    summary.push_str(&format!("   gravity {:.2} m/s²", game.get_gravity()));
+    let mut details = vec![
+        TextLine::from(summary),
+        TextLine::from(format!(
+            "air {:.1} s (max {:.1} s, score {:.1})   angle {:+.0}°   time {:.0}:{:04.1}",
+            stats.airtime,
+            stats.max_airtime,
+            stats.air_score,
+            game.get_car_body_angle().to_degrees(),
+            minutes,
+            stats.time - minutes * 60.0
+        )),
+    ];
+    // The ghost delta and how the run ended go first so they show even when
+    // the rest is cut off
+    if let Some(delta) = app
+        .ghost
+        .as_ref()
+        .and_then(|ghost| GhostDelta::between(game, ghost))
+    {
+        // Past the ghost's farthest point there's no time to compare against
+        let time = delta
+            .time
+            .map_or("--".to_string(), |time| format!("{:+.1}s", time));
+        details.insert(
+            0,
+            TextLine::from(format!("ghost {} / {:+.0}m", time, delta.distance)),
+        );
+    }
+    if let Some(status) = run_status(game) {
+        details.insert(
+            0,
+            TextLine::styled(
+                format!("{}, press r to restart", status),
+                theme.hud.low_fuel,
+            ),
+        );
+    }
+    // Rows the lines wrap to, give or take a word pushed onto the next row
+    let width = details_area.width.max(1) as usize;
+    let rows: usize = details
+        .iter()
+        .map(|line| line.width().div_ceil(width).max(1))
+        .sum();
+    let [stats_area, sparkline_area] =
+        Layout::vertical([Constraint::Length(rows as u16), Constraint::Fill(1)])
+            .areas(details_area);
+    frame.render_widget(
+        Paragraph::new(details).wrap(Wrap { trim: true }),
+        stats_area,
+    );
+
+    // Only show as much history as fits, newest on the right
+    let history = &app.speed_history;
+    let visible = sparkline_area.width as usize;
+    let speeds: Vec<u64> = history
+        .iter()
+        .skip(history.len().saturating_sub(visible))
+        .copied()
+        .collect();
+    frame.render_widget(
+        Sparkline::default()
+            .style(theme.hud.speed)
+            .max((HUD_MAX_SPEED * 10.0) as u64)
+            .bar_set(theme.glyphs.sparkline_bars.set())
+            .data(&speeds),
+        sparkline_area,
+    );
 }
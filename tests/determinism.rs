@@ -0,0 +1,37 @@
+use ratatui_hillclimb::{
+    input::{Input, TimedInput},
+    sim, Car, Game, Level, Replay,
+};
+
+// Replay of a run at full throttle that eases off a step now and then
+fn replay() -> Replay {
+    let mut replay = Replay::of(&Game::new(Car::default(), Level::from_seed(7)));
+    replay.inputs = (0..5)
+        .map(|_| TimedInput {
+            tick: 0,
+            input: Input::Accelerate,
+        })
+        .chain((1..10).flat_map(|i| {
+            [
+                TimedInput {
+                    tick: i * 200,
+                    input: Input::Decelerate,
+                },
+                TimedInput {
+                    tick: i * 200 + 30,
+                    input: Input::Accelerate,
+                },
+            ]
+        }))
+        .collect();
+    replay
+}
+
+#[test]
+fn replaying_twice_gives_the_same_hash() {
+    let replay = replay();
+    let first = sim::run(&mut replay.game(), &replay.inputs, 30.0, None).unwrap();
+    let second = sim::run(&mut replay.game(), &replay.inputs, 30.0, None).unwrap();
+    assert!(first.distance > 0.0);
+    assert_eq!(first.state_hash, second.state_hash);
+}
@@ -0,0 +1,94 @@
+use std::path::Path;
+
+use ratatui_hillclimb::background::{LayerKind, LayerSpec};
+use ratatui_hillclimb::level::{GravityZone, Obstacle, SurfaceSpan};
+use ratatui_hillclimb::surface::Surface;
+use ratatui_hillclimb::Level;
+
+fn load(name: &str) -> Level {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("levels")
+        .join(name);
+    Level::load(&path).unwrap()
+}
+
+// The error `level` fails validation with
+fn validate_error(level: &Level) -> String {
+    match level.validate() {
+        Ok(()) => panic!("level is valid"),
+        Err(error) => format!("{:#}", error),
+    }
+}
+
+#[test]
+fn loads_the_shipped_levels() {
+    for name in ["example.toml", "moon.toml"] {
+        load(name);
+    }
+}
+
+#[test]
+fn rejects_terrain_going_backwards() {
+    let mut level = load("example.toml");
+    level.terrain.swap(1, 2);
+    assert!(validate_error(&level).contains("terrain points must go left to right"));
+}
+
+#[test]
+fn rejects_a_spawn_off_the_track() {
+    let mut level = load("example.toml");
+    level.spawn = 1000.0;
+    assert!(validate_error(&level).contains("spawn at 1000 is off the track"));
+}
+
+#[test]
+fn rejects_a_finish_before_the_spawn() {
+    let mut level = load("example.toml");
+    level.finish = Some(-50.0);
+    assert!(validate_error(&level).contains("must be past the spawn"));
+}
+
+#[test]
+fn rejects_surfaces_out_of_order() {
+    let mut level = load("example.toml");
+    level.surfaces.push(SurfaceSpan {
+        from: 100.0,
+        surface: Surface::Mud,
+    });
+    assert!(validate_error(&level).contains("surfaces must go left to right"));
+}
+
+#[test]
+fn rejects_a_flat_obstacle() {
+    let mut level = load("example.toml");
+    level.obstacles.push(Obstacle {
+        x: 300.0,
+        width: 4.0,
+        height: 0.0,
+    });
+    assert!(validate_error(&level).contains("positive width and height"));
+}
+
+#[test]
+fn rejects_negative_gravity() {
+    let mut level = load("moon.toml");
+    level.gravity_zones.push(GravityZone {
+        from: 400.0,
+        gravity: -1.0,
+    });
+    assert!(validate_error(&level).contains("must have a positive gravity"));
+}
+
+#[test]
+fn rejects_a_background_layer_moving_faster_than_the_camera() {
+    let mut level = load("example.toml");
+    level.background = Some(vec![LayerSpec {
+        kind: LayerKind::Mountains,
+        parallax: 1.5,
+        period: 1000.0,
+        base: 0.0,
+        height: 20.0,
+        count: 0,
+    }]);
+    assert!(validate_error(&level).contains("parallax must be between 0 and 1"));
+}
@@ -0,0 +1,74 @@
+use std::path::PathBuf;
+
+use ratatui_hillclimb::{sim, Car, Game, Level, Replay};
+
+// Replay of a fresh default game
+fn replay() -> Replay {
+    Replay::of(&Game::new(Car::default(), Level::default()))
+}
+
+// Saves `replay` and loads it back, returning the error loading it gives
+fn load_error(name: &str, replay: &Replay) -> String {
+    let path = temp_path(name);
+    replay.save(&path).unwrap();
+    let result = Replay::load(&path);
+    std::fs::remove_file(&path).unwrap();
+    match result {
+        Ok(_) => panic!("replay {} loaded", name),
+        Err(error) => format!("{:#}", error),
+    }
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("hillclimb-{}-{}.toml", name, std::process::id()))
+}
+
+#[test]
+fn loads_a_saved_replay() {
+    let path = temp_path("valid-replay");
+    replay().save(&path).unwrap();
+    let result = Replay::load(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert!(result.is_ok());
+}
+
+#[test]
+fn keeps_the_rewind_count() {
+    let path = temp_path("rewound-replay");
+    let mut replay = replay();
+    replay.rewinds = 2;
+    replay.save(&path).unwrap();
+    let loaded = Replay::load(&path);
+    std::fs::remove_file(&path).unwrap();
+    let loaded = loaded.unwrap();
+    let result = sim::run(&mut loaded.game(), &loaded.inputs, 1.0, None).unwrap();
+    assert_eq!(result.rewinds, 2);
+}
+
+#[test]
+fn rejects_a_zero_time_step() {
+    let mut replay = replay();
+    replay.time_step = 0.0;
+    assert!(load_error("zero-time-step", &replay).contains("time_step must be positive"));
+}
+
+#[test]
+fn rejects_one_point_terrain() {
+    let mut replay = replay();
+    replay.level.terrain = vec![(0.0, 0.0)];
+    assert!(load_error("one-point-terrain", &replay).contains("at least two points"));
+}
+
+#[test]
+fn rejects_a_zero_length_level() {
+    let mut replay = replay();
+    replay.level.length = 0.0;
+    assert!(load_error("zero-length", &replay).contains("length must be positive"));
+}
+
+#[test]
+fn rejects_a_bad_car() {
+    let mut replay = replay();
+    replay.car.rear_wheel_radius = -1.0;
+    assert!(load_error("bad-car", &replay).contains("rear_wheel_radius must be positive"));
+}
@@ -0,0 +1,34 @@
+use ratatui_hillclimb::{input::Input, Car, Game, Level, Snapshot};
+
+// Ticks run before the snapshot is taken, and compared after it
+const TICKS_BEFORE: u64 = 120;
+const TICKS_AFTER: u64 = 300;
+
+#[test]
+fn restored_snapshot_carries_on_like_the_uninterrupted_run() {
+    let mut game = Game::new(Car::default(), Level::default());
+    for _ in 0..5 {
+        game.apply_input(Input::Accelerate);
+    }
+    for _ in 0..TICKS_BEFORE {
+        game.step_physics();
+    }
+
+    let path = std::env::temp_dir().join(format!("hillclimb-snapshot-{}.bin", std::process::id()));
+    game.snapshot().save(&path).unwrap();
+    let snapshot = Snapshot::load(&path);
+    std::fs::remove_file(&path).unwrap();
+    let mut restored = Game::restore(snapshot.unwrap());
+    assert_eq!(restored.state_hash(), game.state_hash());
+
+    for _ in 0..TICKS_AFTER {
+        game.step_physics();
+        restored.step_physics();
+        assert_eq!(
+            restored.state_hash(),
+            game.state_hash(),
+            "differs at tick {}",
+            game.get_tick()
+        );
+    }
+}